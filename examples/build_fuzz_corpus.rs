@@ -0,0 +1,39 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct SpecTest {
+    markdown: String,
+    html: String,
+    example: u32,
+    start_line: u32,
+    end_line: u32,
+    section: String,
+}
+
+/// Regenerate the fuzzing seed corpus in `tests/data/fuzz_corpus` from the spec examples.
+/// Each distinct example input becomes one `example_NNN.md` file.
+fn main() {
+    let test_data = fs::read_to_string("tests/data/tests.json").unwrap();
+    let tests: Vec<SpecTest> = serde_json::from_str(&test_data).unwrap();
+
+    let corpus_dir = "tests/data/fuzz_corpus";
+    fs::create_dir_all(corpus_dir).unwrap();
+
+    let mut seen = HashSet::new();
+    let mut written = 0;
+
+    for test in &tests {
+        // Several examples share the same input; keep only the first
+        if !seen.insert(test.markdown.as_str()) {
+            continue;
+        }
+        let path = format!("{}/example_{:03}.md", corpus_dir, test.example);
+        fs::write(&path, &test.markdown).unwrap();
+        written += 1;
+    }
+
+    println!("Wrote {} seed files to {}", written, corpus_dir);
+}
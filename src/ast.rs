@@ -4,18 +4,39 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Node {
     Document(Vec<Node>),
+    FrontMatter {
+        format: FrontMatterFormat,
+        raw: String, // Content between the delimiter lines
+    }, // Only the first child of a Document, never rendered to HTML
     // Block-level nodes
     Paragraph(Vec<Node>),
     Heading {
         level: u8,
         children: Vec<Node>,
+        attributes: Attributes, // From a trailing {#id .class} block
     },
     CodeBlock {
         info: String,
         literal: String,
+        attributes: Attributes, // From a {#id .class} block after the info string
     },
+    MathBlock(String), // ```math fence, only with ParserOptions::math
     ThematicBreak,
     BlockQuote(Vec<Node>),
+    Alert {
+        kind: AlertKind,
+        children: Vec<Node>, // Block content after the [!KIND] line
+    }, // GitHub alert blockquote, only with ParserOptions::alerts
+    Container {
+        name: String,        // First word after the opening colons
+        args: String,        // Rest of the opening line, trimmed
+        children: Vec<Node>, // Block content
+    }, // ::: name args ... :::, only with ParserOptions::containers
+    LinkReferenceDefinition {
+        label: String,       // Label as written, before normalization
+        destination: String, // Escapes and entities resolved, not percent-encoded
+        title: Option<String>,
+    }, // Only present when ParserOptions::keep_reference_definitions is set
     // List nodes
     UnorderedList {
         tight: bool,         // Tight lists don't add <p> tags in simple items
@@ -30,24 +51,63 @@ pub enum Node {
         tight: bool, // Whether this item should render tightly (no <p> for simple content)
         children: Vec<Node>, // Contains block-level content
     },
+    // Definition list extension nodes
+    DefinitionList(Vec<Node>), // Contains DefinitionTerm and DefinitionDetails nodes
+    DefinitionTerm(Vec<Node>), // Inline content
+    DefinitionDetails {
+        tight: bool,         // Same meaning as for ListItem
+        children: Vec<Node>, // Contains block-level content
+    },
     // Inline nodes
     Text(String),
-    Code(String),        // Inline code span
-    Emphasis(Vec<Node>), // <em> tag
-    Strong(Vec<Node>),   // <strong> tag
+    Code(String),           // Inline code span
+    Emphasis(Vec<Node>),    // <em> tag
+    Strong(Vec<Node>),      // <strong> tag
+    Superscript(Vec<Node>), // ^text^, <sup> tag
+    Subscript(Vec<Node>),   // ~text~, <sub> tag
+    Mark(Vec<Node>),        // ==text==, <mark> tag
+    Insert(Vec<Node>),      // ++text++, <ins> tag
     Link {
         destination: String,
         title: Option<String>,
         children: Vec<Node>,
+        attributes: Attributes,
     },
     Image {
         destination: String,
         title: Option<String>,
         alt_text: Vec<Node>, // Alt text can contain inline elements
+        attributes: Attributes,
     },
-    HardBreak,          // <br /> tag (backslash at end of line)
-    HtmlBlock(String),  // Raw HTML block (passed through unchanged)
+    WikiLink {
+        page: String,           // Page name, empty for a link within the current page
+        anchor: Option<String>, // After `#`
+        children: Vec<Node>,    // The alias after `|`, or the target as written
+    }, // [[Page#anchor|alias]], only with ParserOptions::wiki_links
+    Span {
+        children: Vec<Node>,
+        attributes: Attributes,
+    }, // [text]{#id .class}, only with ParserOptions::attributes
+    Emoji {
+        shortcode: String, // Without the colons
+        unicode: String,
+    }, // :shortcode:, only with ParserOptions::emoji
+    Mention(String), // @username, only with ParserOptions::mentions
+    Reference {
+        kind: ReferenceKind,
+        repository: Option<String>, // owner/repo of a cross-repository issue reference
+        id: String,                 // Issue number or tag name, without the #
+    }, // #123, owner/repo#123 or #tag, only with ParserOptions::mentions
+    Abbreviation {
+        text: String,
+        title: String, // Expansion from the *[text]: title definition
+    }, // Only with ParserOptions::abbreviations
+    HardBreak,       // <br /> tag (backslash at end of line)
+    HtmlBlock(String), // Raw HTML block (passed through unchanged)
     HtmlInline(String), // Raw HTML inline (passed through unchanged)
+    // Math extension nodes (content is TeX, kept verbatim)
+    InlineMath(String),  // $...$
+    DisplayMath(String), // $$...$$
     // GFM extension nodes
     Table {
         alignments: Vec<Alignment>, // Column alignments
@@ -60,6 +120,20 @@ pub enum Node {
     },
 }
 
+/// Attributes from a `{#id .class key=value}` block
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub pairs: Vec<(String, String)>, // Other key=value attributes, in source order
+}
+
+impl Attributes {
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none() && self.classes.is_empty() && self.pairs.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Alignment {
     None,
@@ -67,3 +141,90 @@ pub enum Alignment {
     Right,
     Center,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReferenceKind {
+    Issue, // #123
+    Tag,   // #tag
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlertKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl AlertKind {
+    /// Kind for a marker name such as `NOTE` (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "note" => Some(AlertKind::Note),
+            "tip" => Some(AlertKind::Tip),
+            "important" => Some(AlertKind::Important),
+            "warning" => Some(AlertKind::Warning),
+            "caution" => Some(AlertKind::Caution),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used in CSS class names
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertKind::Note => "note",
+            AlertKind::Tip => "tip",
+            AlertKind::Important => "important",
+            AlertKind::Warning => "warning",
+            AlertKind::Caution => "caution",
+        }
+    }
+
+    /// Title shown above the alert content
+    pub fn title(&self) -> &'static str {
+        match self {
+            AlertKind::Note => "Note",
+            AlertKind::Tip => "Tip",
+            AlertKind::Important => "Important",
+            AlertKind::Warning => "Warning",
+            AlertKind::Caution => "Caution",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FrontMatterFormat {
+    Yaml, // Delimited by --- (closed by --- or ...)
+    Toml, // Delimited by +++
+}
+
+#[cfg(feature = "front-matter")]
+impl FrontMatterFormat {
+    /// Parse raw front matter into a JSON value
+    pub fn parse(&self, raw: &str) -> Result<serde_json::Value, String> {
+        match self {
+            FrontMatterFormat::Yaml => serde_yaml_ng::from_str(raw).map_err(|e| e.to_string()),
+            FrontMatterFormat::Toml => toml::from_str(raw).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "front-matter"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_values() {
+        let yaml = FrontMatterFormat::Yaml.parse("title: Home\ntags: [a, b]\n");
+        assert_eq!(
+            yaml,
+            Ok(serde_json::json!({"title": "Home", "tags": ["a", "b"]}))
+        );
+
+        let toml = FrontMatterFormat::Toml.parse("title = \"Home\"\nweight = 3\n");
+        assert_eq!(toml, Ok(serde_json::json!({"title": "Home", "weight": 3})));
+
+        assert!(FrontMatterFormat::Toml.parse("title = ").is_err());
+    }
+}
@@ -0,0 +1,703 @@
+//! Versioned JSON format for the AST, for consumers outside Rust.
+//!
+//! A tree is written as `{"version": 1, "document": <node>}`. Every node is an object
+//! with a `type` naming the variant in snake_case plus its fields under the names used
+//! in [`Node`]:
+//!
+//! | `type` | fields |
+//! | --- | --- |
+//! | `document`, `paragraph`, `block_quote`, `definition_list`, `definition_term`, `emphasis`, `strong`, `superscript`, `subscript`, `mark`, `insert`, `table_row` | `children` |
+//! | `front_matter` | `format` (`yaml` or `toml`), `raw` |
+//! | `heading` | `level` (1 to 6), `children`, `attributes`? |
+//! | `code_block` | `info`, `literal`, `attributes`? |
+//! | `thematic_break`, `hard_break` | |
+//! | `alert` | `kind` (`note`, `tip`, `important`, `warning` or `caution`), `children` |
+//! | `container` | `name`, `args`, `children` |
+//! | `unordered_list` | `tight`, `children` |
+//! | `ordered_list` | `start`, `tight`, `children` |
+//! | `list_item`, `definition_details` | `tight`, `children` |
+//! | `text`, `code`, `html_block`, `html_inline`, `inline_math`, `display_math`, `math_block` | `literal` |
+//! | `link` | `destination`, `title`?, `children`, `attributes`? |
+//! | `image` | `destination`, `title`?, `alt_text`, `attributes`? |
+//! | `wiki_link` | `page`, `anchor`?, `children` |
+//! | `span` | `children`, `attributes`? |
+//! | `emoji` | `shortcode`, `unicode` |
+//! | `mention` | `username` |
+//! | `reference` | `kind` (`issue` or `tag`), `repository`?, `id` |
+//! | `abbreviation` | `text`, `title` |
+//! | `link_reference_definition` | `label`, `destination`, `title`? |
+//! | `table` | `alignments` (each `none`, `left`, `right` or `center`), `children` (`table_row`s with one cell per alignment) |
+//! | `table_cell` | `is_header`, `children` |
+//!
+//! Fields marked `?` are left out when absent or empty. `attributes` is an object with
+//! an optional `id`, a `classes` array of strings and a `pairs` array of `[key, value]`
+//! arrays. Readers should ignore fields they don't know; the version is only raised
+//! for changes that older readers would misread.
+use crate::ast::{AlertKind, Alignment, Attributes, FrontMatterFormat, Node, ReferenceKind};
+use serde_json::{Map, Value, json};
+
+/// Version written by [`to_json`] and the newest one [`from_json`] reads
+pub const JSON_FORMAT_VERSION: u64 = 1;
+
+/// Serialize a tree in the versioned JSON format
+pub fn to_json(node: &Node) -> String {
+    json!({
+        "version": JSON_FORMAT_VERSION,
+        "document": node_to_value(node),
+    })
+    .to_string()
+}
+
+/// Read a tree written by [`to_json`]
+pub fn from_json(json: &str) -> Result<Node, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or("missing format version")?;
+    if version > JSON_FORMAT_VERSION {
+        return Err(format!("unsupported format version {}", version));
+    }
+    let document = value.get("document").ok_or("missing document")?;
+    value_to_node(document)
+}
+
+fn node_to_value(node: &Node) -> Value {
+    let children = |nodes: &[Node]| Value::Array(nodes.iter().map(node_to_value).collect());
+    let mut object = Map::new();
+    let mut set = |key: &str, value: Value| {
+        object.insert(key.to_string(), value);
+    };
+
+    let node_type = match node {
+        Node::Document(nodes) => {
+            set("children", children(nodes));
+            "document"
+        }
+        Node::FrontMatter { format, raw } => {
+            let format = match format {
+                FrontMatterFormat::Yaml => "yaml",
+                FrontMatterFormat::Toml => "toml",
+            };
+            set("format", json!(format));
+            set("raw", json!(raw));
+            "front_matter"
+        }
+        Node::Paragraph(nodes) => {
+            set("children", children(nodes));
+            "paragraph"
+        }
+        Node::Heading {
+            level,
+            children: nodes,
+            attributes,
+        } => {
+            set("level", json!(level));
+            set("children", children(nodes));
+            if !attributes.is_empty() {
+                set("attributes", attributes_to_value(attributes));
+            }
+            "heading"
+        }
+        Node::CodeBlock {
+            info,
+            literal,
+            attributes,
+        } => {
+            set("info", json!(info));
+            set("literal", json!(literal));
+            if !attributes.is_empty() {
+                set("attributes", attributes_to_value(attributes));
+            }
+            "code_block"
+        }
+        Node::MathBlock(literal) => {
+            set("literal", json!(literal));
+            "math_block"
+        }
+        Node::ThematicBreak => "thematic_break",
+        Node::BlockQuote(nodes) => {
+            set("children", children(nodes));
+            "block_quote"
+        }
+        Node::Alert {
+            kind,
+            children: nodes,
+        } => {
+            set("kind", json!(kind.name()));
+            set("children", children(nodes));
+            "alert"
+        }
+        Node::Container {
+            name,
+            args,
+            children: nodes,
+        } => {
+            set("name", json!(name));
+            set("args", json!(args));
+            set("children", children(nodes));
+            "container"
+        }
+        Node::UnorderedList {
+            tight,
+            children: nodes,
+        } => {
+            set("tight", json!(tight));
+            set("children", children(nodes));
+            "unordered_list"
+        }
+        Node::OrderedList {
+            start,
+            tight,
+            children: nodes,
+        } => {
+            set("start", json!(start));
+            set("tight", json!(tight));
+            set("children", children(nodes));
+            "ordered_list"
+        }
+        Node::ListItem {
+            tight,
+            children: nodes,
+        } => {
+            set("tight", json!(tight));
+            set("children", children(nodes));
+            "list_item"
+        }
+        Node::DefinitionList(nodes) => {
+            set("children", children(nodes));
+            "definition_list"
+        }
+        Node::DefinitionTerm(nodes) => {
+            set("children", children(nodes));
+            "definition_term"
+        }
+        Node::DefinitionDetails {
+            tight,
+            children: nodes,
+        } => {
+            set("tight", json!(tight));
+            set("children", children(nodes));
+            "definition_details"
+        }
+        Node::Text(literal) => {
+            set("literal", json!(literal));
+            "text"
+        }
+        Node::Code(literal) => {
+            set("literal", json!(literal));
+            "code"
+        }
+        Node::Emphasis(nodes) => {
+            set("children", children(nodes));
+            "emphasis"
+        }
+        Node::Strong(nodes) => {
+            set("children", children(nodes));
+            "strong"
+        }
+        Node::Superscript(nodes) => {
+            set("children", children(nodes));
+            "superscript"
+        }
+        Node::Subscript(nodes) => {
+            set("children", children(nodes));
+            "subscript"
+        }
+        Node::Mark(nodes) => {
+            set("children", children(nodes));
+            "mark"
+        }
+        Node::Insert(nodes) => {
+            set("children", children(nodes));
+            "insert"
+        }
+        Node::Link {
+            destination,
+            title,
+            children: nodes,
+            attributes,
+        } => {
+            set("destination", json!(destination));
+            if let Some(title) = title {
+                set("title", json!(title));
+            }
+            set("children", children(nodes));
+            if !attributes.is_empty() {
+                set("attributes", attributes_to_value(attributes));
+            }
+            "link"
+        }
+        Node::Image {
+            destination,
+            title,
+            alt_text,
+            attributes,
+        } => {
+            set("destination", json!(destination));
+            if let Some(title) = title {
+                set("title", json!(title));
+            }
+            set("alt_text", children(alt_text));
+            if !attributes.is_empty() {
+                set("attributes", attributes_to_value(attributes));
+            }
+            "image"
+        }
+        Node::WikiLink {
+            page,
+            anchor,
+            children: nodes,
+        } => {
+            set("page", json!(page));
+            if let Some(anchor) = anchor {
+                set("anchor", json!(anchor));
+            }
+            set("children", children(nodes));
+            "wiki_link"
+        }
+        Node::Span {
+            children: nodes,
+            attributes,
+        } => {
+            set("children", children(nodes));
+            if !attributes.is_empty() {
+                set("attributes", attributes_to_value(attributes));
+            }
+            "span"
+        }
+        Node::Emoji { shortcode, unicode } => {
+            set("shortcode", json!(shortcode));
+            set("unicode", json!(unicode));
+            "emoji"
+        }
+        Node::Mention(username) => {
+            set("username", json!(username));
+            "mention"
+        }
+        Node::Reference {
+            kind,
+            repository,
+            id,
+        } => {
+            let kind = match kind {
+                ReferenceKind::Issue => "issue",
+                ReferenceKind::Tag => "tag",
+            };
+            set("kind", json!(kind));
+            if let Some(repository) = repository {
+                set("repository", json!(repository));
+            }
+            set("id", json!(id));
+            "reference"
+        }
+        Node::Abbreviation { text, title } => {
+            set("text", json!(text));
+            set("title", json!(title));
+            "abbreviation"
+        }
+        Node::HardBreak => "hard_break",
+        Node::HtmlBlock(literal) => {
+            set("literal", json!(literal));
+            "html_block"
+        }
+        Node::LinkReferenceDefinition {
+            label,
+            destination,
+            title,
+        } => {
+            set("label", json!(label));
+            set("destination", json!(destination));
+            if let Some(title) = title {
+                set("title", json!(title));
+            }
+            "link_reference_definition"
+        }
+        Node::HtmlInline(literal) => {
+            set("literal", json!(literal));
+            "html_inline"
+        }
+        Node::InlineMath(literal) => {
+            set("literal", json!(literal));
+            "inline_math"
+        }
+        Node::DisplayMath(literal) => {
+            set("literal", json!(literal));
+            "display_math"
+        }
+        Node::Table {
+            alignments,
+            children: nodes,
+        } => {
+            let alignments: Vec<&str> = alignments
+                .iter()
+                .map(|alignment| match alignment {
+                    Alignment::None => "none",
+                    Alignment::Left => "left",
+                    Alignment::Right => "right",
+                    Alignment::Center => "center",
+                })
+                .collect();
+            set("alignments", json!(alignments));
+            set("children", children(nodes));
+            "table"
+        }
+        Node::TableRow(nodes) => {
+            set("children", children(nodes));
+            "table_row"
+        }
+        Node::TableCell {
+            is_header,
+            children: nodes,
+        } => {
+            set("is_header", json!(is_header));
+            set("children", children(nodes));
+            "table_cell"
+        }
+    };
+
+    object.insert("type".to_string(), json!(node_type));
+    Value::Object(object)
+}
+
+fn attributes_to_value(attributes: &Attributes) -> Value {
+    let mut object = Map::new();
+    if let Some(id) = &attributes.id {
+        object.insert("id".to_string(), json!(id));
+    }
+    object.insert("classes".to_string(), json!(attributes.classes));
+    object.insert("pairs".to_string(), json!(attributes.pairs));
+    Value::Object(object)
+}
+
+/// Field access on a node object, with errors naming the node type
+struct Fields<'a> {
+    node_type: &'a str,
+    object: &'a Map<String, Value>,
+}
+
+impl Fields<'_> {
+    fn missing(&self, key: &str) -> String {
+        format!("{}: missing or invalid `{}`", self.node_type, key)
+    }
+
+    fn string(&self, key: &str) -> Result<String, String> {
+        self.optional_string(key)?.ok_or_else(|| self.missing(key))
+    }
+
+    fn optional_string(&self, key: &str) -> Result<Option<String>, String> {
+        match self.object.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(self.missing(key)),
+        }
+    }
+
+    fn bool(&self, key: &str) -> Result<bool, String> {
+        self.object
+            .get(key)
+            .and_then(Value::as_bool)
+            .ok_or_else(|| self.missing(key))
+    }
+
+    fn number<T: TryFrom<u64>>(&self, key: &str) -> Result<T, String> {
+        self.object
+            .get(key)
+            .and_then(Value::as_u64)
+            .and_then(|n| T::try_from(n).ok())
+            .ok_or_else(|| self.missing(key))
+    }
+
+    fn nodes(&self, key: &str) -> Result<Vec<Node>, String> {
+        self.object
+            .get(key)
+            .and_then(Value::as_array)
+            .ok_or_else(|| self.missing(key))?
+            .iter()
+            .map(value_to_node)
+            .collect()
+    }
+
+    fn children(&self) -> Result<Vec<Node>, String> {
+        self.nodes("children")
+    }
+
+    fn attributes(&self) -> Result<Attributes, String> {
+        let object = match self.object.get("attributes") {
+            None | Some(Value::Null) => return Ok(Attributes::default()),
+            Some(Value::Object(object)) => object,
+            Some(_) => return Err(self.missing("attributes")),
+        };
+        let fields = Fields {
+            node_type: "attributes",
+            object,
+        };
+        let strings = |key: &str| -> Result<Vec<Value>, String> {
+            match object.get(key) {
+                None => Ok(Vec::new()),
+                Some(Value::Array(values)) => Ok(values.clone()),
+                Some(_) => Err(fields.missing(key)),
+            }
+        };
+        let classes = strings("classes")?
+            .iter()
+            .map(|class| class.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| fields.missing("classes"))?;
+        let pairs = strings("pairs")?
+            .iter()
+            .map(|pair| match pair.as_array().map(Vec::as_slice) {
+                Some([Value::String(key), Value::String(value)]) => {
+                    Some((key.clone(), value.clone()))
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| fields.missing("pairs"))?;
+        Ok(Attributes {
+            id: fields.optional_string("id")?,
+            classes,
+            pairs,
+        })
+    }
+}
+
+fn value_to_node(value: &Value) -> Result<Node, String> {
+    let object = value.as_object().ok_or("node is not an object")?;
+    let node_type = object
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or("node without a type")?;
+    let fields = Fields { node_type, object };
+
+    let node = match node_type {
+        "document" => Node::Document(fields.children()?),
+        "front_matter" => Node::FrontMatter {
+            format: match fields.string("format")?.as_str() {
+                "yaml" => FrontMatterFormat::Yaml,
+                "toml" => FrontMatterFormat::Toml,
+                _ => return Err(fields.missing("format")),
+            },
+            raw: fields.string("raw")?,
+        },
+        "paragraph" => Node::Paragraph(fields.children()?),
+        "heading" => Node::Heading {
+            level: match fields.number("level")? {
+                level @ 1..=6 => level,
+                _ => return Err(fields.missing("level")),
+            },
+            children: fields.children()?,
+            attributes: fields.attributes()?,
+        },
+        "code_block" => Node::CodeBlock {
+            info: fields.string("info")?,
+            literal: fields.string("literal")?,
+            attributes: fields.attributes()?,
+        },
+        "math_block" => Node::MathBlock(fields.string("literal")?),
+        "thematic_break" => Node::ThematicBreak,
+        "block_quote" => Node::BlockQuote(fields.children()?),
+        "alert" => Node::Alert {
+            kind: AlertKind::from_name(&fields.string("kind")?)
+                .ok_or_else(|| fields.missing("kind"))?,
+            children: fields.children()?,
+        },
+        "container" => Node::Container {
+            name: fields.string("name")?,
+            args: fields.string("args")?,
+            children: fields.children()?,
+        },
+        "unordered_list" => Node::UnorderedList {
+            tight: fields.bool("tight")?,
+            children: fields.children()?,
+        },
+        "ordered_list" => Node::OrderedList {
+            start: fields.number("start")?,
+            tight: fields.bool("tight")?,
+            children: fields.children()?,
+        },
+        "list_item" => Node::ListItem {
+            tight: fields.bool("tight")?,
+            children: fields.children()?,
+        },
+        "definition_list" => Node::DefinitionList(fields.children()?),
+        "definition_term" => Node::DefinitionTerm(fields.children()?),
+        "definition_details" => Node::DefinitionDetails {
+            tight: fields.bool("tight")?,
+            children: fields.children()?,
+        },
+        "text" => Node::Text(fields.string("literal")?),
+        "code" => Node::Code(fields.string("literal")?),
+        "emphasis" => Node::Emphasis(fields.children()?),
+        "strong" => Node::Strong(fields.children()?),
+        "superscript" => Node::Superscript(fields.children()?),
+        "subscript" => Node::Subscript(fields.children()?),
+        "mark" => Node::Mark(fields.children()?),
+        "insert" => Node::Insert(fields.children()?),
+        "link" => Node::Link {
+            destination: fields.string("destination")?,
+            title: fields.optional_string("title")?,
+            children: fields.children()?,
+            attributes: fields.attributes()?,
+        },
+        "image" => Node::Image {
+            destination: fields.string("destination")?,
+            title: fields.optional_string("title")?,
+            alt_text: fields.nodes("alt_text")?,
+            attributes: fields.attributes()?,
+        },
+        "wiki_link" => Node::WikiLink {
+            page: fields.string("page")?,
+            anchor: fields.optional_string("anchor")?,
+            children: fields.children()?,
+        },
+        "span" => Node::Span {
+            children: fields.children()?,
+            attributes: fields.attributes()?,
+        },
+        "emoji" => Node::Emoji {
+            shortcode: fields.string("shortcode")?,
+            unicode: fields.string("unicode")?,
+        },
+        "mention" => Node::Mention(fields.string("username")?),
+        "reference" => Node::Reference {
+            kind: match fields.string("kind")?.as_str() {
+                "issue" => ReferenceKind::Issue,
+                "tag" => ReferenceKind::Tag,
+                _ => return Err(fields.missing("kind")),
+            },
+            repository: fields.optional_string("repository")?,
+            id: fields.string("id")?,
+        },
+        "abbreviation" => Node::Abbreviation {
+            text: fields.string("text")?,
+            title: fields.string("title")?,
+        },
+        "hard_break" => Node::HardBreak,
+        "html_block" => Node::HtmlBlock(fields.string("literal")?),
+        "link_reference_definition" => Node::LinkReferenceDefinition {
+            label: fields.string("label")?,
+            destination: fields.string("destination")?,
+            title: fields.optional_string("title")?,
+        },
+        "html_inline" => Node::HtmlInline(fields.string("literal")?),
+        "inline_math" => Node::InlineMath(fields.string("literal")?),
+        "display_math" => Node::DisplayMath(fields.string("literal")?),
+        "table" => {
+            let alignments: Vec<Alignment> = object
+                .get("alignments")
+                .and_then(Value::as_array)
+                .ok_or_else(|| fields.missing("alignments"))?
+                .iter()
+                .map(|alignment| match alignment.as_str() {
+                    Some("none") => Ok(Alignment::None),
+                    Some("left") => Ok(Alignment::Left),
+                    Some("right") => Ok(Alignment::Right),
+                    Some("center") => Ok(Alignment::Center),
+                    _ => Err(fields.missing("alignments")),
+                })
+                .collect::<Result<_, _>>()?;
+            let children = fields.children()?;
+            let is_row = |row: &Node| match row {
+                Node::TableRow(cells) => cells.len() == alignments.len(),
+                _ => false,
+            };
+            if !children.iter().all(is_row) {
+                return Err("table: every row needs one cell per alignment".to_string());
+            }
+            Node::Table {
+                alignments,
+                children,
+            }
+        }
+        "table_row" => Node::TableRow(fields.children()?),
+        "table_cell" => Node::TableCell {
+            is_header: fields.bool("is_header")?,
+            children: fields.children()?,
+        },
+        _ => return Err(format!("unknown node type `{}`", node_type)),
+    };
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Parser, ParserOptions};
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json(&Parser::new().parse("# Hi\n\n***\n")),
+            "{\"document\":{\"children\":[{\"children\":[{\"literal\":\"Hi\",\"type\":\"text\"}],\
+             \"level\":1,\"type\":\"heading\"},{\"type\":\"thematic_break\"}],\
+             \"type\":\"document\"},\"version\":1}"
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut parser = Parser::with_options(ParserOptions {
+            front_matter: true,
+            attributes: true,
+            alerts: true,
+            math: true,
+            emoji: true,
+            mentions: true,
+            wiki_links: true,
+            definition_lists: true,
+            keep_reference_definitions: true,
+            ..Default::default()
+        });
+        let ast = parser.parse(
+            "---\ntitle: x\n---\n\n\
+             # Title {#top .big}\n\n\
+             > [!WARNING]\n> Careful :tada: @ann #12 [[Page#a|b]] $x^2$\n\n\
+             3. [a](/u \"t\") ![i](/i.png)\\\n   <b>x</b>\n\n\
+             Term\n: Details\n\n\
+             | a | b |\n|:-|-:|\n| `c` | d |\n\n\
+             [ref]: /r\n",
+        );
+        assert_eq!(from_json(&to_json(&ast)), Ok(ast));
+    }
+
+    #[test]
+    fn test_json_round_trip_ragged_tables() {
+        let ast =
+            Parser::new().parse("| a | b |\n|---|---|\n| c |\n| d | e | f |\n\na|b|c\n|---|\n");
+        assert_eq!(from_json(&to_json(&ast)), Ok(ast));
+    }
+
+    #[test]
+    fn test_from_json_fragment() {
+        assert_eq!(
+            from_json("{\"version\":1,\"document\":{\"type\":\"text\",\"literal\":\"x\"}}"),
+            Ok(Node::Text("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        assert_eq!(
+            from_json("{\"version\":2,\"document\":{\"type\":\"document\",\"children\":[]}}"),
+            Err("unsupported format version 2".to_string())
+        );
+        assert_eq!(
+            from_json("{\"version\":1,\"document\":{\"type\":\"heading\",\"children\":[]}}"),
+            Err("heading: missing or invalid `level`".to_string())
+        );
+        assert_eq!(
+            from_json(
+                "{\"version\":1,\"document\":{\"type\":\"heading\",\"level\":7,\"children\":[]}}"
+            ),
+            Err("heading: missing or invalid `level`".to_string())
+        );
+        assert_eq!(
+            from_json(
+                "{\"version\":1,\"document\":{\"type\":\"table\",\"alignments\":[\"none\"],\
+                 \"children\":[{\"type\":\"table_row\",\"children\":[]}]}}"
+            ),
+            Err("table: every row needs one cell per alignment".to_string())
+        );
+    }
+}
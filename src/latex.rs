@@ -0,0 +1,494 @@
+use crate::ast::Alignment;
+/// LaTeX renderer for CommonMark AST
+use crate::ast::Node;
+use crate::plain_text::is_block;
+use crate::renderer::reference_text;
+
+/// Counters used by nested `enumerate` environments, outermost first
+const ENUM_COUNTERS: [&str; 4] = ["enumi", "enumii", "enumiii", "enumiv"];
+
+/// Heading commands by level; levels 5 and 6 share `\subparagraph`
+const SECTIONS: [&str; 6] = [
+    "section",
+    "subsection",
+    "subsubsection",
+    "paragraph",
+    "subparagraph",
+    "subparagraph",
+];
+
+/// Options controlling the LaTeX renderer
+#[derive(Debug, Clone, Default)]
+pub struct LatexOptions {
+    /// Wrap the output in a complete `article` document with the packages it needs
+    pub standalone: bool,
+    /// Render code blocks as `lstlisting` (with the info string as the language)
+    /// instead of `verbatim`
+    pub listings: bool,
+}
+
+/// Renders a tree as LaTeX body text. Without `standalone`, the including document
+/// needs `hyperref`, `graphicx` and `soul` (and `listings` with that option).
+pub struct LatexRenderer {
+    options: LatexOptions,
+}
+
+impl LatexRenderer {
+    pub fn new() -> Self {
+        Self::with_options(LatexOptions::default())
+    }
+
+    pub fn with_options(options: LatexOptions) -> Self {
+        LatexRenderer { options }
+    }
+
+    pub fn render(&self, node: &Node) -> String {
+        let body = match node {
+            Node::Document(children) => self.render_blocks(children, 0),
+            _ => self.render_blocks(std::slice::from_ref(node), 0),
+        };
+        let body = if body.is_empty() { body } else { body + "\n" };
+        if !self.options.standalone {
+            return body;
+        }
+
+        let mut document = String::from(
+            "\\documentclass{article}\n\
+             \\usepackage[T1]{fontenc}\n\
+             \\usepackage[utf8]{inputenc}\n\
+             \\usepackage{amsmath}\n\
+             \\usepackage{graphicx}\n\
+             \\usepackage{soul}\n",
+        );
+        if self.options.listings {
+            document.push_str("\\usepackage{listings}\n");
+        }
+        document.push_str("\\usepackage{hyperref}\n\\begin{document}\n\n");
+        document.push_str(&body);
+        document.push_str("\n\\end{document}\n");
+        document
+    }
+
+    fn render_inline(&self, nodes: &[Node]) -> String {
+        nodes
+            .iter()
+            .map(|node| self.render_inline_node(node))
+            .collect()
+    }
+
+    fn render_inline_node(&self, node: &Node) -> String {
+        let command = |name: &str, children: &[Node]| {
+            format!("\\{}{{{}}}", name, self.render_inline(children))
+        };
+        match node {
+            Node::Text(text) => escape_latex(text),
+            Node::Code(code) => format!("\\texttt{{{}}}", escape_latex(code)),
+            Node::Emphasis(children) => command("emph", children),
+            Node::Strong(children) => command("textbf", children),
+            Node::Superscript(children) => command("textsuperscript", children),
+            Node::Subscript(children) => command("textsubscript", children),
+            Node::Mark(children) => command("hl", children),
+            Node::Insert(children) => command("underline", children),
+            Node::Link {
+                destination,
+                children,
+                ..
+            } => format!(
+                "\\href{{{}}}{{{}}}",
+                escape_url(destination),
+                self.render_inline(children)
+            ),
+            Node::Image { destination, .. } => {
+                format!(
+                    "\\includegraphics{{{}}}",
+                    escape_url(&image_path(destination))
+                )
+            }
+            // Pages of a wiki have no place in a single document; keep just the text,
+            // linked when it points within this one
+            Node::WikiLink {
+                page,
+                anchor: Some(anchor),
+                children,
+            } if page.is_empty() => format!(
+                "\\hyperref[{}]{{{}}}",
+                escape_label(anchor),
+                self.render_inline(children)
+            ),
+            Node::WikiLink { children, .. } => self.render_inline(children),
+            Node::Span {
+                children,
+                attributes,
+            } => match &attributes.id {
+                Some(id) => format!(
+                    "\\label{{{}}}{}",
+                    escape_label(id),
+                    self.render_inline(children)
+                ),
+                None => self.render_inline(children),
+            },
+            Node::Emoji { unicode, .. } => unicode.clone(),
+            Node::Mention(_) | Node::Reference { .. } => escape_latex(&reference_text(node)),
+            Node::Abbreviation { text, .. } => escape_latex(text),
+            Node::HardBreak => "\\\\\n".to_string(),
+            Node::InlineMath(math) => format!("${}$", math),
+            Node::DisplayMath(math) => format!("\\[{}\\]", math),
+            // Raw HTML has no LaTeX equivalent
+            _ => String::new(),
+        }
+    }
+
+    /// Render a sequence of blocks separated by blank lines. `depth` is the number of
+    /// enclosing ordered lists, which picks the counter a `start` number sets.
+    fn render_blocks(&self, nodes: &[Node], depth: usize) -> String {
+        let mut blocks = Vec::new();
+        let mut inline = Vec::new();
+
+        for node in nodes {
+            if is_block(node) {
+                if !inline.is_empty() {
+                    blocks.push(self.render_inline(&inline));
+                    inline.clear();
+                }
+                blocks.push(self.render_block(node, depth));
+            } else {
+                inline.push(node.clone());
+            }
+        }
+        if !inline.is_empty() {
+            blocks.push(self.render_inline(&inline));
+        }
+
+        blocks.retain(|block| !block.is_empty());
+        blocks.join("\n\n")
+    }
+
+    fn render_block(&self, node: &Node, depth: usize) -> String {
+        match node {
+            Node::Document(children) | Node::Container { children, .. } => {
+                self.render_blocks(children, depth)
+            }
+            Node::Paragraph(children) => self.render_inline(children),
+            Node::Heading {
+                level,
+                children,
+                attributes,
+            } => {
+                let section = SECTIONS[(*level as usize).clamp(1, 6) - 1];
+                let label = match &attributes.id {
+                    Some(id) => format!("\\label{{{}}}", escape_label(id)),
+                    None => String::new(),
+                };
+                format!("\\{}{{{}}}{}", section, self.render_inline(children), label)
+            }
+            Node::CodeBlock { info, literal, .. } => {
+                let language = info.split_whitespace().next().unwrap_or("");
+                if self.options.listings {
+                    let options = if language.is_empty() {
+                        String::new()
+                    } else {
+                        format!("[language={}]", language)
+                    };
+                    format!(
+                        "\\begin{{lstlisting}}{}\n{}\\end{{lstlisting}}",
+                        options, literal
+                    )
+                } else {
+                    format!("\\begin{{verbatim}}\n{}\\end{{verbatim}}", literal)
+                }
+            }
+            Node::MathBlock(math) => format!("\\[{}\\]", math),
+            Node::ThematicBreak => {
+                "\\begin{center}\\rule{0.5\\linewidth}{0.5pt}\\end{center}".to_string()
+            }
+            Node::BlockQuote(children) => {
+                environment("quote", &self.render_blocks(children, depth))
+            }
+            Node::Alert { kind, children } => {
+                let content = self.render_blocks(children, depth);
+                environment(
+                    "quote",
+                    &format!("\\textbf{{{}}}\n\n{}", kind.title(), content),
+                )
+            }
+            Node::UnorderedList { children, .. } => {
+                environment("itemize", &self.render_items(children, depth))
+            }
+            Node::OrderedList {
+                start, children, ..
+            } => {
+                let mut content = String::new();
+                // Items are numbered from one past the counter's value
+                if *start != 1
+                    && let Some(counter) = ENUM_COUNTERS.get(depth)
+                {
+                    content.push_str(&format!(
+                        "\\setcounter{{{}}}{{{}}}\n",
+                        counter,
+                        start.saturating_sub(1)
+                    ));
+                }
+                content.push_str(&self.render_items(children, depth + 1));
+                environment("enumerate", &content)
+            }
+            Node::ListItem { .. } => self.render_items(std::slice::from_ref(node), depth),
+            Node::DefinitionList(children) => {
+                let mut items = Vec::new();
+                for child in children {
+                    match child {
+                        Node::DefinitionTerm(term) => {
+                            items.push(format!("\\item[{{{}}}]", self.render_inline(term)))
+                        }
+                        Node::DefinitionDetails { children, .. } => {
+                            items.push(self.render_blocks(children, depth))
+                        }
+                        _ => {}
+                    }
+                }
+                environment("description", &items.join("\n"))
+            }
+            Node::Table {
+                alignments,
+                children,
+            } => self.render_table(alignments, children),
+            // Raw HTML and metadata have no LaTeX equivalent
+            Node::HtmlBlock(_)
+            | Node::FrontMatter { .. }
+            | Node::LinkReferenceDefinition { .. } => String::new(),
+            _ => self.render_inline_node(node),
+        }
+    }
+
+    /// Render list items as `\item` lines, each followed by its content
+    fn render_items(&self, items: &[Node], depth: usize) -> String {
+        let items: Vec<String> = items
+            .iter()
+            .map(|item| {
+                let content = match item {
+                    Node::ListItem { children, .. } => self.render_blocks(children, depth),
+                    _ => self.render_block(item, depth),
+                };
+                if content.is_empty() {
+                    "\\item".to_string()
+                } else {
+                    // The empty group keeps a leading `[` from being read as a label
+                    format!("\\item{{}} {}", content)
+                }
+            })
+            .collect();
+        items.join("\n")
+    }
+
+    /// Render a table as a `tabular` with a rule under the header row
+    fn render_table(&self, alignments: &[Alignment], rows: &[Node]) -> String {
+        let spec: String = alignments
+            .iter()
+            .map(|alignment| match alignment {
+                Alignment::Right => 'r',
+                Alignment::Center => 'c',
+                Alignment::Left | Alignment::None => 'l',
+            })
+            .collect();
+
+        let mut lines = vec![
+            format!("\\begin{{tabular}}{{{}}}", spec),
+            "\\hline".to_string(),
+        ];
+        for (i, row) in rows.iter().enumerate() {
+            if let Node::TableRow(cells) = row {
+                let cells: Vec<String> = cells
+                    .iter()
+                    .map(|cell| match cell {
+                        Node::TableCell { children, .. } => self.render_inline(children),
+                        _ => String::new(),
+                    })
+                    .collect();
+                lines.push(format!("{} \\\\", cells.join(" & ")));
+            }
+            if i == 0 {
+                lines.push("\\hline".to_string());
+            }
+        }
+        lines.push("\\hline".to_string());
+        lines.push("\\end{tabular}".to_string());
+        lines.join("\n")
+    }
+}
+
+impl Default for LatexRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn environment(name: &str, content: &str) -> String {
+    format!("\\begin{{{}}}\n{}\n\\end{{{}}}", name, content, name)
+}
+
+/// Escape characters that are special in LaTeX text
+fn escape_latex(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str("\\textbackslash{}"),
+            '~' => result.push_str("\\textasciitilde{}"),
+            '^' => result.push_str("\\textasciicircum{}"),
+            '{' | '}' | '$' | '&' | '%' | '#' | '_' => {
+                result.push('\\');
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Escape a URL for `\href` and `\includegraphics`, where only these characters
+/// need a backslash
+fn escape_url(url: &str) -> String {
+    let mut result = String::with_capacity(url.len());
+    for c in url.chars() {
+        if matches!(c, '\\' | '{' | '}' | '%' | '#') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Path of a local image with its percent-encoding undone, so that it names the file
+/// on disk; URLs are kept as they are
+fn image_path(destination: &str) -> String {
+    let is_url = destination.starts_with("//")
+        || destination.split_once(':').is_some_and(|(scheme, _)| {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        });
+    if is_url {
+        return destination.to_string();
+    }
+
+    let bytes = destination.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Keep only characters that are safe in a `\label` name
+fn escape_label(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| !matches!(c, '\\' | '{' | '}' | '%' | '#' | '~' | '^' | '$' | '&'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Parser, ParserOptions};
+
+    #[test]
+    fn test_latex_escaping() {
+        let mut parser = Parser::with_options(ParserOptions {
+            attributes: true,
+            ..Default::default()
+        });
+        let ast = parser.parse(
+            "# Costs & *fees* {#costs}\n\n\
+             Pay 50% of $x_1 to [us](https://example.com/#pay) ![logo](logo.png)\n",
+        );
+        assert_eq!(
+            LatexRenderer::new().render(&ast),
+            "\\section{Costs \\& \\emph{fees}}\\label{costs}\n\n\
+             Pay 50\\% of \\$x\\_1 to \\href{https://example.com/\\#pay}{us} \
+             \\includegraphics{logo.png}\n"
+        );
+    }
+
+    #[test]
+    fn test_latex_lists_and_quotes() {
+        let ast = Parser::new().parse("3. three\n4. four\n   1. nested\n\n> - item\n");
+        assert_eq!(
+            LatexRenderer::new().render(&ast),
+            "\\begin{enumerate}\n\\setcounter{enumi}{2}\n\\item{} three\n\\item{} four\n\n\
+             \\begin{enumerate}\n\\item{} nested\n\\end{enumerate}\n\\end{enumerate}\n\n\
+             \\begin{quote}\n\\begin{itemize}\n\\item{} item\n\\end{itemize}\n\\end{quote}\n"
+        );
+    }
+
+    #[test]
+    fn test_latex_brackets_in_items_and_terms() {
+        let mut parser = Parser::with_options(ParserOptions {
+            definition_lists: true,
+            ..Default::default()
+        });
+        let ast = parser.parse(
+            "- [x] done
+
+Term [a]
+: Details
+",
+        );
+        assert_eq!(
+            LatexRenderer::new().render(&ast),
+            "\\begin{itemize}\n\\item{} [x] done\n\\end{itemize}\n\n\
+             \\begin{description}\n\\item[{Term [a]}]\nDetails\n\\end{description}\n"
+        );
+    }
+
+    #[test]
+    fn test_latex_image_paths() {
+        let ast = Parser::new().parse("![p](<my image.png>) ![q](https://example.com/a%20b.png)\n");
+        assert_eq!(
+            LatexRenderer::new().render(&ast),
+            "\\includegraphics{my image.png} \\includegraphics{https://example.com/a\\%20b.png}\n"
+        );
+    }
+
+    #[test]
+    fn test_latex_code_and_tables() {
+        let ast = Parser::new().parse(
+            "```\na\\b\n```\n\n\
+             | A | B | C |\n| :-- | :-: | --: |\n| 1 | 2 | 3 |\n",
+        );
+        assert_eq!(
+            LatexRenderer::new().render(&ast),
+            "\\begin{verbatim}\na\\b\n\\end{verbatim}\n\n\
+             \\begin{tabular}{lcr}\n\\hline\nA & B & C \\\\\n\\hline\n1 & 2 & 3 \\\\\n\
+             \\hline\n\\end{tabular}\n"
+        );
+    }
+
+    #[test]
+    fn test_latex_standalone_with_listings() {
+        let renderer = LatexRenderer::with_options(LatexOptions {
+            standalone: true,
+            listings: true,
+        });
+        let document = renderer.render(&Parser::new().parse("```rust\nfn main() {}\n```\n"));
+        assert!(document.starts_with("\\documentclass{article}\n"));
+        assert!(
+            document
+                .contains("\\begin{lstlisting}[language=rust]\nfn main() {}\n\\end{lstlisting}\n")
+        );
+        assert!(document.ends_with("\\end{document}\n"));
+    }
+}
@@ -1,7 +1,14 @@
 /// A CommonMark-compliant Markdown parser and renderer
 pub mod ast;
+pub mod emoji;
+pub mod json;
+pub mod latex;
+pub mod man;
 pub mod parser;
+pub mod plain_text;
 pub mod renderer;
+pub mod terminal;
+pub mod xml;
 
 use parser::Parser;
 use renderer::HtmlRenderer;
@@ -14,6 +21,12 @@ pub fn markdown_to_html(markdown: &str) -> String {
     renderer.render(&ast)
 }
 
+/// Parse `markdown` with `options` and render it with the default HTML renderer
+#[cfg(test)]
+pub(crate) fn render_with(options: parser::ParserOptions, markdown: &str) -> String {
+    HtmlRenderer::new().render(&Parser::with_options(options).parse(markdown))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +74,131 @@ mod tests {
             "<p><a href=\"http://foo.bar.baz\">http://foo.bar.baz</a></p>\n"
         );
     }
+
+    #[test]
+    fn test_parser_reuse_does_not_leak_references() {
+        let mut parser = Parser::new();
+        let renderer = HtmlRenderer::new();
+
+        let first = parser.parse("[foo]: /url\n\n[foo]\n");
+        assert_eq!(renderer.render(&first), "<p><a href=\"/url\">foo</a></p>\n");
+
+        let second = parser.parse("[foo]\n");
+        assert_eq!(renderer.render(&second), "<p>[foo]</p>\n");
+
+        // Nested blocks still see the enclosing document's definitions
+        let nested = parser.parse("[foo]: /url\n\n> [foo]\n");
+        assert_eq!(
+            renderer.render(&nested),
+            "<blockquote>\n<p><a href=\"/url\">foo</a></p>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn test_predefined_references() {
+        let mut parser = Parser::new();
+        parser.add_reference_definition("Home Page", "/wiki/home", Some("Home"));
+        let renderer = HtmlRenderer::new();
+
+        let ast = parser.parse("[home page] and ![logo][home page]\n");
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"/wiki/home\" title=\"Home\">home page</a> and \
+             <img src=\"/wiki/home\" alt=\"logo\" title=\"Home\" /></p>\n"
+        );
+
+        // Definitions in the document win over predefined ones
+        let ast = parser.parse("[Home Page]\n\n[home page]: /local\n");
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"/local\">Home Page</a></p>\n"
+        );
+
+        // ...and predefined ones survive across documents until cleared
+        let ast = parser.parse("[Home Page]\n");
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"/wiki/home\" title=\"Home\">Home Page</a></p>\n"
+        );
+        parser.clear_reference_definitions();
+        let ast = parser.parse("[Home Page]\n");
+        assert_eq!(renderer.render(&ast), "<p>[Home Page]</p>\n");
+    }
+
+    #[test]
+    fn test_broken_link_callback() {
+        let mut parser = Parser::new();
+        parser.set_broken_link_callback(|label| {
+            (label != "Missing").then(|| (format!("/wiki/{}", label), None))
+        });
+        let renderer = HtmlRenderer::new();
+
+        let ast = parser.parse("[PageName], [see][Other Page] and [Missing]\n");
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"/wiki/PageName\">PageName</a>, \
+             <a href=\"/wiki/Other%20Page\">see</a> and [Missing]</p>\n"
+        );
+
+        // Defined references never reach the callback
+        let ast = parser.parse("[PageName]\n\n[pagename]: /defined\n");
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"/defined\">PageName</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_keep_reference_definitions() {
+        use crate::ast::Node;
+        use crate::parser::ParserOptions;
+
+        let input = "[Foo Bar]: /url \"Title\"\n\n> [baz]: <my url>\n";
+        let options = ParserOptions {
+            keep_reference_definitions: true,
+            ..Default::default()
+        };
+        let ast = Parser::with_options(options).parse(input);
+        assert_eq!(
+            ast,
+            Node::Document(vec![
+                Node::LinkReferenceDefinition {
+                    label: "Foo Bar".to_string(),
+                    destination: "/url".to_string(),
+                    title: Some("Title".to_string()),
+                },
+                Node::BlockQuote(vec![Node::LinkReferenceDefinition {
+                    label: "baz".to_string(),
+                    destination: "my url".to_string(),
+                    title: None,
+                }]),
+            ])
+        );
+
+        // Off by default
+        assert_eq!(
+            Parser::new().parse(input),
+            Node::Document(vec![Node::BlockQuote(vec![])])
+        );
+    }
+
+    #[test]
+    fn test_url_rewriter() {
+        use crate::renderer::UrlKind;
+
+        let mut renderer = HtmlRenderer::new();
+        renderer.set_url_rewriter(|kind, url| match kind {
+            UrlKind::Image => format!("https://cdn.example.com/{}", url),
+            UrlKind::Link if url.ends_with(".md") => url.replace(".md", ".html"),
+            UrlKind::Link => url.to_string(),
+        });
+
+        let ast = Parser::new().parse("[guide](guide.md) ![a](a.png) <http://x.org/?a&b>\n");
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"guide.html\">guide</a> \
+             <img src=\"https://cdn.example.com/a.png\" alt=\"a\" /> \
+             <a href=\"http://x.org/?a&amp;b\">http://x.org/?a&amp;b</a></p>\n"
+        );
+    }
 }
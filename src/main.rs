@@ -1,11 +1,73 @@
+use conformark::json::to_json;
+use conformark::latex::LatexRenderer;
+use conformark::man::{ManOptions, ManRenderer};
 use conformark::markdown_to_html;
+use conformark::parser::Parser;
+use conformark::terminal::{TerminalOptions, TerminalRenderer};
+use conformark::xml::XmlRenderer;
 use std::io::{self, Read};
+use std::process;
+
+const USAGE: &str =
+    "Usage: conformark [--to html|json|latex|man|terminal|xml] [--title NAME] < input.md";
 
 fn main() {
+    let mut format = String::from("html");
+    // Page name for the man page's `.TH` line
+    let mut title = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--to" {
+            format = args.next().unwrap_or_else(|| usage_error());
+        } else if let Some(value) = arg.strip_prefix("--to=") {
+            format = value.to_string();
+        } else if arg == "--title" {
+            title = Some(args.next().unwrap_or_else(|| usage_error()));
+        } else if let Some(value) = arg.strip_prefix("--title=") {
+            title = Some(value.to_string());
+        } else if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            return;
+        } else {
+            usage_error();
+        }
+    }
+
     let mut input = String::new();
     io::stdin()
         .read_to_string(&mut input)
         .expect("Failed to read stdin");
-    let output = markdown_to_html(&input);
+
+    let output = match format.as_str() {
+        "html" => markdown_to_html(&input),
+        "json" => to_json(&Parser::new().parse(&input)) + "\n",
+        "latex" => LatexRenderer::new().render(&Parser::new().parse(&input)),
+        "man" => {
+            let renderer = ManRenderer::with_options(ManOptions {
+                title,
+                ..Default::default()
+            });
+            renderer.render(&Parser::new().parse(&input))
+        }
+        "terminal" => {
+            // Wrap to the terminal width when the shell exports it
+            let width = std::env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+                .unwrap_or(80);
+            let renderer = TerminalRenderer::with_options(TerminalOptions {
+                width,
+                ..Default::default()
+            });
+            renderer.render(&Parser::new().parse(&input))
+        }
+        "xml" => XmlRenderer::new().render(&Parser::new().parse(&input)),
+        _ => usage_error(),
+    };
     print!("{}", output);
 }
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,405 @@
+use crate::ast::Alignment;
+/// roff man page renderer for CommonMark AST
+use crate::ast::Node;
+use crate::plain_text::{PlainTextRenderer, is_block};
+use crate::renderer::reference_text;
+
+/// Options controlling the man page renderer
+#[derive(Debug, Clone)]
+pub struct ManOptions {
+    /// Page name for the `.TH` line; no `.TH` line is written without it
+    pub title: Option<String>,
+    /// Manual section, such as `1` for commands
+    pub section: String,
+    pub date: Option<String>,
+    /// Package and version the page belongs to, shown in the footer
+    pub source: Option<String>,
+    /// Name of the manual, shown in the header
+    pub manual: Option<String>,
+}
+
+impl Default for ManOptions {
+    fn default() -> Self {
+        ManOptions {
+            title: None,
+            section: "1".to_string(),
+            date: None,
+            source: None,
+            manual: None,
+        }
+    }
+}
+
+/// Renders a tree as groff `man` macros, in the style of cmark's man output:
+/// level 1 headings become `.SH`, deeper ones `.SS`, and list items `.IP`
+pub struct ManRenderer {
+    options: ManOptions,
+}
+
+impl ManRenderer {
+    pub fn new() -> Self {
+        Self::with_options(ManOptions::default())
+    }
+
+    pub fn with_options(options: ManOptions) -> Self {
+        ManRenderer { options }
+    }
+
+    pub fn render(&self, node: &Node) -> String {
+        let body = match node {
+            Node::Document(children) => self.render_blocks(children),
+            _ => self.render_blocks(std::slice::from_ref(node)),
+        };
+
+        let mut lines = Vec::new();
+        // Tables need tbl, which man only runs when asked to on the first line. Text
+        // lines starting with `.` are escaped, so `.TS` only comes from a table.
+        if body.lines().any(|line| line == ".TS") {
+            lines.push("'\\\" t".to_string());
+        }
+        if let Some(title) = &self.options.title {
+            let mut args = vec![
+                title.as_str(),
+                self.options.section.as_str(),
+                self.options.date.as_deref().unwrap_or(""),
+                self.options.source.as_deref().unwrap_or(""),
+                self.options.manual.as_deref().unwrap_or(""),
+            ];
+            while args.last() == Some(&"") {
+                args.pop();
+            }
+            let args: Vec<String> = args
+                .iter()
+                .map(|arg| format!("\"{}\"", escape_roff(arg).replace('"', "\\(dq")))
+                .collect();
+            lines.push(format!(".TH {}", args.join(" ")));
+        }
+        if !body.is_empty() {
+            lines.push(body);
+        }
+        if lines.is_empty() {
+            String::new()
+        } else {
+            lines.join("\n") + "\n"
+        }
+    }
+
+    fn render_inline(&self, nodes: &[Node]) -> String {
+        nodes
+            .iter()
+            .map(|node| self.render_inline_node(node))
+            .collect()
+    }
+
+    fn render_inline_node(&self, node: &Node) -> String {
+        match node {
+            Node::Text(text) => escape_roff(text),
+            Node::Code(code) => format!("\\f[C]{}\\f[]", escape_roff(code)),
+            Node::Emphasis(children) => format!("\\fI{}\\fP", self.render_inline(children)),
+            Node::Strong(children) => format!("\\fB{}\\fP", self.render_inline(children)),
+            Node::Superscript(children)
+            | Node::Subscript(children)
+            | Node::Mark(children)
+            | Node::Insert(children)
+            | Node::WikiLink { children, .. }
+            | Node::Span { children, .. } => self.render_inline(children),
+            Node::Link {
+                destination,
+                children,
+                ..
+            } => {
+                let text = self.render_inline(children);
+                let plain = PlainTextRenderer::new().render_inline(children);
+                if plain == *destination || destination.strip_prefix("mailto:") == Some(&plain) {
+                    text
+                } else {
+                    format!("{} (\\fI{}\\fP)", text, escape_roff(destination))
+                }
+            }
+            Node::Image { alt_text, .. } => {
+                format!("[IMAGE: {}]", self.render_inline(alt_text))
+            }
+            Node::Emoji { unicode, .. } => unicode.clone(),
+            Node::Mention(_) | Node::Reference { .. } => escape_roff(&reference_text(node)),
+            Node::Abbreviation { text, .. } => escape_roff(text),
+            Node::InlineMath(math) | Node::DisplayMath(math) => escape_roff(math),
+            Node::HardBreak => "\n.br\n".to_string(),
+            // Raw HTML has no roff equivalent
+            _ => String::new(),
+        }
+    }
+
+    /// Render a sequence of blocks, each starting on a line of its own
+    fn render_blocks(&self, nodes: &[Node]) -> String {
+        let mut blocks = Vec::new();
+        let mut inline = Vec::new();
+
+        for node in nodes {
+            if is_block(node) {
+                if !inline.is_empty() {
+                    blocks.push(format!(".PP\n{}", self.render_inline(&inline)));
+                    inline.clear();
+                }
+                blocks.push(self.render_block(node));
+            } else {
+                inline.push(node.clone());
+            }
+        }
+        if !inline.is_empty() {
+            blocks.push(format!(".PP\n{}", self.render_inline(&inline)));
+        }
+
+        blocks.retain(|block| !block.is_empty());
+        blocks.join("\n")
+    }
+
+    fn render_block(&self, node: &Node) -> String {
+        match node {
+            Node::Document(children) | Node::Container { children, .. } => {
+                self.render_blocks(children)
+            }
+            Node::Paragraph(children) => format!(".PP\n{}", self.render_inline(children)),
+            Node::Heading {
+                level, children, ..
+            } => {
+                let macro_name = if *level == 1 { ".SH" } else { ".SS" };
+                format!("{}\n{}", macro_name, self.render_inline(children))
+            }
+            Node::CodeBlock { literal, .. } => {
+                format!(".IP\n.nf\n\\f[C]\n{}\\f[]\n.fi", escape_roff(literal))
+            }
+            Node::MathBlock(math) => {
+                format!(".IP\n.nf\n{}\n.fi", escape_roff(math))
+            }
+            Node::ThematicBreak => ".PP\n  *  *  *  *  *".to_string(),
+            Node::BlockQuote(children) => format!(".RS\n{}\n.RE", self.render_blocks(children)),
+            Node::Alert { kind, children } => format!(
+                ".RS\n.PP\n\\fB{}\\fP\n{}\n.RE",
+                kind.title(),
+                self.render_blocks(children)
+            ),
+            Node::UnorderedList { children, .. } => {
+                let items: Vec<String> = children
+                    .iter()
+                    .map(|item| self.render_list_item(item, "\\[bu]", 2))
+                    .collect();
+                items.join("\n")
+            }
+            Node::OrderedList {
+                start, children, ..
+            } => {
+                let items: Vec<String> = children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let marker = format!("\"{}.\"", *start as usize + i);
+                        self.render_list_item(item, &marker, 4)
+                    })
+                    .collect();
+                items.join("\n")
+            }
+            Node::ListItem { .. } => self.render_list_item(node, "\\[bu]", 2),
+            Node::DefinitionList(children) => {
+                let parts: Vec<String> = children
+                    .iter()
+                    .map(|child| match child {
+                        Node::DefinitionTerm(term) => {
+                            format!(".TP\n\\fB{}\\fP", self.render_inline(term))
+                        }
+                        Node::DefinitionDetails { children, .. } => {
+                            self.render_indented(children, 7)
+                        }
+                        _ => self.render_block(child),
+                    })
+                    .collect();
+                parts.join("\n")
+            }
+            Node::Table {
+                alignments,
+                children,
+            } => self.render_table(alignments, children),
+            // Raw HTML and metadata have no roff equivalent
+            Node::HtmlBlock(_)
+            | Node::FrontMatter { .. }
+            | Node::LinkReferenceDefinition { .. } => String::new(),
+            _ => format!(".PP\n{}", self.render_inline_node(node)),
+        }
+    }
+
+    /// Render a list item as an `.IP` paragraph tagged with `marker`
+    fn render_list_item(&self, item: &Node, marker: &str, indent: usize) -> String {
+        let content = match item {
+            Node::ListItem { children, .. } => self.render_indented(children, indent),
+            _ => self.render_block(item),
+        };
+        format!(".IP {} {}\n{}", marker, indent, content)
+    }
+
+    /// Render the content of a tagged paragraph (`.IP` or `.TP`): a leading paragraph
+    /// continues the tagged one, later paragraphs are `.IP` so they keep its indent, and
+    /// other blocks are shifted right by `indent` with `.RS`/`.RE`
+    fn render_indented(&self, nodes: &[Node], indent: usize) -> String {
+        let mut parts = Vec::new();
+        let mut inline = Vec::new();
+        let flush = |inline: &mut Vec<Node>, parts: &mut Vec<String>| {
+            if !inline.is_empty() {
+                parts.push(self.render_inline(inline));
+                inline.clear();
+            }
+        };
+
+        for node in nodes {
+            match node {
+                Node::Paragraph(children) => {
+                    flush(&mut inline, &mut parts);
+                    parts.push(self.render_inline(children));
+                }
+                _ if is_block(node) => {
+                    flush(&mut inline, &mut parts);
+                    let block = self.render_block(node);
+                    if !block.is_empty() {
+                        parts.push(format!(".RS {}\n{}\n.RE", indent, block));
+                    }
+                }
+                _ => inline.push(node.clone()),
+            }
+        }
+        flush(&mut inline, &mut parts);
+
+        let mut result = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                result.push('\n');
+                // Blocks open with a macro; paragraphs need one to start a new paragraph
+                if !part.starts_with(".RS") {
+                    result.push_str(".IP\n");
+                }
+            }
+            result.push_str(part);
+        }
+        result
+    }
+
+    /// Render a table for the `tbl` preprocessor, with a bold header row
+    fn render_table(&self, alignments: &[Alignment], rows: &[Node]) -> String {
+        let columns: Vec<&str> = alignments
+            .iter()
+            .map(|alignment| match alignment {
+                Alignment::Right => "r",
+                Alignment::Center => "c",
+                Alignment::Left | Alignment::None => "l",
+            })
+            .collect();
+        let header: Vec<String> = columns.iter().map(|c| format!("{}B", c)).collect();
+
+        let mut lines = vec![
+            ".TS".to_string(),
+            "tab(\t);".to_string(),
+            header.join(" "),
+            format!("{}.", columns.join(" ")),
+        ];
+        for (i, row) in rows.iter().enumerate() {
+            if let Node::TableRow(cells) = row {
+                let cells: Vec<String> = cells
+                    .iter()
+                    .map(|cell| match cell {
+                        Node::TableCell { children, .. } => {
+                            self.render_inline(children).replace(['\n', '\t'], " ")
+                        }
+                        _ => String::new(),
+                    })
+                    .collect();
+                lines.push(cells.join("\t"));
+            }
+            if i == 0 {
+                lines.push("_".to_string());
+            }
+        }
+        lines.push(".TE".to_string());
+        lines.join("\n")
+    }
+}
+
+impl Default for ManRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Escape backslashes and hyphens, and guard lines that would otherwise start with
+/// a control character (`.` or `'`)
+fn escape_roff(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut line_start = true;
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str("\\e"),
+            '-' => result.push_str("\\-"),
+            '.' | '\'' if line_start => {
+                result.push_str("\\&");
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+        line_start = c == '\n';
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_man_page() {
+        let ast = Parser::new().parse(
+            "# Name\n\n\
+             tool \\- a *small* **tool**\n.dotfile on a new line\n\n\
+             ## Options\n\n\
+             - `--verbose`\n- see [docs](https://example.com)\n\n\
+             3. first\n\n   more\n\n\
+             > quoted\n\n\
+             ```\n.x\n```\n",
+        );
+        let renderer = ManRenderer::with_options(ManOptions {
+            title: Some("TOOL".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            renderer.render(&ast),
+            ".TH \"TOOL\" \"1\"\n\
+             .SH\nName\n\
+             .PP\ntool \\- a \\fIsmall\\fP \\fBtool\\fP\n\\&.dotfile on a new line\n\
+             .SS\nOptions\n\
+             .IP \\[bu] 2\n\\f[C]\\-\\-verbose\\f[]\n\
+             .IP \\[bu] 2\nsee docs (\\fIhttps://example.com\\fP)\n\
+             .IP \"3.\" 4\nfirst\n.IP\nmore\n\
+             .RS\n.PP\nquoted\n.RE\n\
+             .IP\n.nf\n\\f[C]\n\\&.x\n\\f[]\n.fi\n"
+        );
+    }
+
+    #[test]
+    fn test_man_tables_request_tbl() {
+        let renderer = ManRenderer::with_options(ManOptions {
+            title: Some("TOOL".to_string()),
+            ..Default::default()
+        });
+        let page = renderer.render(&Parser::new().parse("| A |\n| - |\n| 1 |\n"));
+        assert!(
+            page.starts_with("'\\\" t\n.TH \"TOOL\" \"1\"\n"),
+            "{}",
+            page
+        );
+        assert!(page.contains("\n.TS\n"), "{}", page);
+
+        let page = renderer.render(&Parser::new().parse("```\n.TS\n```\n"));
+        assert!(page.starts_with(".TH"), "{}", page);
+    }
+
+    #[test]
+    fn test_man_empty_document() {
+        assert_eq!(ManRenderer::new().render(&Parser::new().parse("")), "");
+    }
+}
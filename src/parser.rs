@@ -1,9 +1,23 @@
+use crate::ast::AlertKind;
 use crate::ast::Alignment;
+use crate::ast::Attributes;
+use crate::ast::FrontMatterFormat;
 /// CommonMark parser implementation
 use crate::ast::Node;
-use std::collections::HashMap;
+use crate::ast::ReferenceKind;
+use crate::emoji;
+use crate::plain_text::alt_text_to_string;
+use std::collections::{HashMap, HashSet};
 use unicode_casefold::UnicodeCaseFold;
 
+const LEFT_SINGLE_QUOTE: char = '\u{2018}';
+const RIGHT_SINGLE_QUOTE: char = '\u{2019}';
+const LEFT_DOUBLE_QUOTE: char = '\u{201C}';
+const RIGHT_DOUBLE_QUOTE: char = '\u{201D}';
+const EN_DASH: char = '\u{2013}';
+const EM_DASH: char = '\u{2014}';
+const ELLIPSIS: char = '\u{2026}';
+
 /// Delimiter run on the stack for emphasis processing
 #[derive(Debug, Clone)]
 struct DelimiterRun {
@@ -15,19 +29,247 @@ struct DelimiterRun {
     active: bool,
 }
 
+/// A delimiter paired on the delimiter stack like emphasis, but into its own node.
+/// Only runs of exactly `length` characters open or close.
+struct InlineDelimiter {
+    delimiter: char,
+    length: usize,
+    node: fn(Vec<Node>) -> Node,
+    enabled: fn(&ParserOptions) -> bool,
+}
+
+/// Optional delimiters handled by the delimiter stack besides `*`, `_` and smart quotes
+const INLINE_DELIMITERS: [InlineDelimiter; 4] = [
+    InlineDelimiter {
+        delimiter: '^',
+        length: 1,
+        node: Node::Superscript,
+        enabled: |options| options.superscript,
+    },
+    InlineDelimiter {
+        delimiter: '~',
+        length: 1,
+        node: Node::Subscript,
+        enabled: |options| options.subscript,
+    },
+    InlineDelimiter {
+        delimiter: '=',
+        length: 2,
+        node: Node::Mark,
+        enabled: |options| options.highlight,
+    },
+    InlineDelimiter {
+        delimiter: '+',
+        length: 2,
+        node: Node::Insert,
+        enabled: |options| options.insert,
+    },
+];
+
+/// Options controlling which syntax and nodes the parser produces
+#[derive(Debug, Clone, Default)]
+pub struct ParserOptions {
+    /// Keep link reference definitions in the tree as `Node::LinkReferenceDefinition`
+    /// instead of dropping them after resolution
+    pub keep_reference_definitions: bool,
+    /// Convert straight quotes to curly quotes, `--`/`---` to en/em dashes and `...` to
+    /// an ellipsis (like cmark's `--smart`)
+    pub smart: bool,
+    /// Parse `$inline$` and `$$display$$` math and ```` ```math ```` fences
+    pub math: bool,
+    /// Recognize a leading YAML (`---`) or TOML (`+++`) front matter block
+    pub front_matter: bool,
+    /// Parse PHP Markdown Extra style definition lists (`Term` then `: definition`)
+    pub definition_lists: bool,
+    /// Parse `{#id .class key=value}` attribute blocks after headings, fenced code block
+    /// info strings, links and images, and bracketed spans (`[text]{.class}`). Blocks
+    /// with an `on*` event handler key are not attributes.
+    pub attributes: bool,
+    /// Turn blockquotes whose first line is `[!NOTE]`, `[!TIP]`, `[!IMPORTANT]`,
+    /// `[!WARNING]` or `[!CAUTION]` into GitHub alerts
+    pub alerts: bool,
+    /// Parse `::: name args` ... `:::` fenced containers; a container closes at a line of
+    /// at least as many colons, so nested containers use fewer colons than their parent
+    pub containers: bool,
+    /// Parse `^superscript^`
+    pub superscript: bool,
+    /// Parse `~subscript~`
+    pub subscript: bool,
+    /// Parse `==highlighted==` text
+    pub highlight: bool,
+    /// Parse `++inserted++` text
+    pub insert: bool,
+    /// Replace `:shortcode:` emoji with `Node::Emoji`
+    pub emoji: bool,
+    /// Parse `[[Page]]`, `[[Page#anchor]]` and `[[Page|alias]]` wiki links
+    pub wiki_links: bool,
+    /// Recognize `@user` mentions, `#123` and `owner/repo#123` issue references and
+    /// `#tag` hashtags in text outside code and links
+    pub mentions: bool,
+    /// Collect `*[HTML]: Hyper Text Markup Language` definitions and mark up the
+    /// abbreviations wherever they appear as whole words
+    pub abbreviations: bool,
+    /// Parse a trailing `{#custom-id .class}` block on ATX and setext headings (implied
+    /// by `attributes`)
+    pub heading_ids: bool,
+    /// Give every heading without an explicit id one generated from its text, made
+    /// unique within the document by appending `-1`, `-2`, ...
+    pub auto_heading_ids: bool,
+}
+
 pub struct Parser {
+    options: ParserOptions,
     /// Link reference definitions: label -> (destination, title)
     reference_definitions: HashMap<String, (String, Option<String>)>,
+    /// Reference definitions shared by every parsed document (same layout as above)
+    predefined_references: HashMap<String, (String, Option<String>)>,
+    /// Called with the label of a reference link or image that has no definition
+    broken_link_callback: Option<BrokenLinkCallback>,
+    /// Abbreviation definitions: abbreviation -> title
+    abbreviations: HashMap<String, String>,
 }
 
+/// Resolves an undefined reference label to `(destination, title)`, or `None` to leave
+/// the brackets as literal text
+pub type BrokenLinkCallback = Box<dyn Fn(&str) -> Option<(String, Option<String>)> + Send + Sync>;
+
 impl Parser {
     pub fn new() -> Self {
+        Self::with_options(ParserOptions::default())
+    }
+
+    pub fn with_options(options: ParserOptions) -> Self {
         Parser {
+            options,
             reference_definitions: HashMap::new(),
+            predefined_references: HashMap::new(),
+            broken_link_callback: None,
+            abbreviations: HashMap::new(),
+        }
+    }
+
+    /// Add a link reference definition that every document parsed by this parser can
+    /// resolve against. Definitions in the document itself take precedence.
+    /// The destination is percent-encoded like one written in the document.
+    pub fn add_reference_definition(
+        &mut self,
+        label: &str,
+        destination: &str,
+        title: Option<&str>,
+    ) {
+        let label = Self::normalize_label(label);
+        if label.is_empty() {
+            return;
+        }
+        self.predefined_references.insert(
+            label,
+            (url_encode(destination), title.map(|t| t.to_string())),
+        );
+    }
+
+    /// Set a callback used to resolve `[text][label]`, `[label][]` and `[label]` references
+    /// (and their image forms) whose label is not defined
+    pub fn set_broken_link_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&str) -> Option<(String, Option<String>)> + Send + Sync + 'static,
+    {
+        self.broken_link_callback = Some(Box::new(callback));
+    }
+
+    /// Remove all reference definitions added with `add_reference_definition`
+    pub fn clear_reference_definitions(&mut self) {
+        self.predefined_references.clear();
+    }
+
+    /// Clear per-document state so the parser can be reused for another document
+    fn reset(&mut self) {
+        self.reference_definitions.clear();
+        self.abbreviations.clear();
+    }
+
+    /// Look up a normalized label, preferring definitions from the current document
+    fn lookup_reference(&self, label: &str) -> Option<&(String, Option<String>)> {
+        self.reference_definitions
+            .get(label)
+            .or_else(|| self.predefined_references.get(label))
+    }
+
+    /// Resolve a reference by its normalized label, falling back to the broken link
+    /// callback (which receives the label as written) when no definition matches.
+    /// Destinations returned by the callback are percent-encoded.
+    fn resolve_reference(&self, label: &str, label_text: &str) -> Option<(String, Option<String>)> {
+        if label.is_empty() {
+            return None;
         }
+        if let Some(definition) = self.lookup_reference(label) {
+            return Some(definition.clone());
+        }
+        self.broken_link_callback
+            .as_ref()
+            .and_then(|callback| callback(label_text.trim()))
+            .map(|(destination, title)| (url_encode(&destination), title))
     }
 
     pub fn parse(&mut self, input: &str) -> Node {
+        self.reset();
+
+        if self.options.front_matter
+            && let Some((front_matter, body)) = self.split_front_matter(input)
+        {
+            let mut document = self.parse_blocks(body);
+            if let Node::Document(children) = &mut document {
+                children.insert(0, front_matter);
+            }
+            if self.options.auto_heading_ids {
+                assign_heading_ids(&mut document);
+            }
+            return document;
+        }
+
+        let mut document = self.parse_blocks(input);
+        if self.options.auto_heading_ids {
+            assign_heading_ids(&mut document);
+        }
+        document
+    }
+
+    /// Split a leading front matter block from the input
+    /// The opening line must be exactly `---` (YAML) or `+++` (TOML) and must be closed by
+    /// the same delimiter (or `...` for YAML); otherwise there is no front matter.
+    /// Returns the `FrontMatter` node and the remaining input.
+    fn split_front_matter<'a>(&self, input: &'a str) -> Option<(Node, &'a str)> {
+        let mut lines = input.split_inclusive('\n');
+        let opening = lines.next()?.trim_end();
+        let format = match opening {
+            "---" => FrontMatterFormat::Yaml,
+            "+++" => FrontMatterFormat::Toml,
+            _ => return None,
+        };
+
+        let raw_start = input.find('\n')? + 1;
+        let mut offset = raw_start;
+        for line in lines {
+            let delimiter = line.trim_end();
+            let closes = match format {
+                FrontMatterFormat::Yaml => delimiter == "---" || delimiter == "...",
+                FrontMatterFormat::Toml => delimiter == "+++",
+            };
+            if closes {
+                let raw = input[raw_start..offset].to_string();
+                return Some((
+                    Node::FrontMatter { format, raw },
+                    &input[offset + line.len()..],
+                ));
+            }
+            offset += line.len();
+        }
+
+        None
+    }
+
+    /// Parse block content; called recursively for block quote and list item contents,
+    /// which share the enclosing document's reference definitions
+    fn parse_blocks(&mut self, input: &str) -> Node {
         let lines: Vec<&str> = input.lines().collect();
 
         // FIRST PASS: Collect all link reference definitions
@@ -114,7 +356,9 @@ impl Parser {
                 let content_lines: Vec<&str> = content.lines().collect();
                 let mut k = 0;
                 while k < content_lines.len() {
-                    if let Some(lines_consumed) =
+                    if self.try_parse_abbreviation_definition(content_lines[k]) {
+                        k += 1;
+                    } else if let Some(lines_consumed) =
                         self.try_parse_link_reference_definition(&content_lines[k..])
                     {
                         k += lines_consumed;
@@ -128,6 +372,13 @@ impl Parser {
                 continue;
             }
 
+            // Abbreviation definitions can appear anywhere outside code
+            if self.try_parse_abbreviation_definition(line) {
+                i += 1;
+                prev_was_ref_def = true;
+                continue;
+            }
+
             // Link reference definitions cannot interrupt a paragraph
             // Check if previous line could be part of a paragraph (non-blank, not a block structure)
             // Exception: if previous line was a ref def, we can have consecutive ref defs
@@ -169,9 +420,18 @@ impl Parser {
             let line = lines[i];
 
             // Skip link reference definitions (already processed, won't modify state)
-            if let Some(lines_consumed) = self.try_parse_link_reference_definition(&lines[i..]) {
+            if let Some((definition, lines_consumed)) =
+                self.parse_link_reference_definition(&lines[i..])
+            {
+                if self.options.keep_reference_definitions {
+                    blocks.push(definition);
+                }
                 i += lines_consumed;
             }
+            // Skip abbreviation definitions (already collected)
+            else if self.parse_abbreviation_definition(line).is_some() {
+                i += 1;
+            }
             // Try to parse ATX heading first
             else if let Some(heading) = self.parse_atx_heading(line) {
                 blocks.push(heading);
@@ -201,6 +461,18 @@ impl Parser {
                 blocks.push(list);
                 i += lines_consumed;
             }
+            // Try to parse fenced container (`::: name`)
+            else if let Some(colons) = self.is_container_start(line) {
+                let (container, lines_consumed) = self.parse_container(&lines[i..], colons);
+                blocks.push(container);
+                i += lines_consumed;
+            }
+            // Try to parse definition list (term lines followed by `: definition`)
+            else if self.is_definition_list_start(&lines[i..]) {
+                let (list, lines_consumed) = self.parse_definition_list(&lines[i..]);
+                blocks.push(list);
+                i += lines_consumed;
+            }
             // Try to parse fenced code block (before indented code block)
             else if let Some((fence_char, fence_len, indent)) = self.is_fenced_code_start(line) {
                 let (code_block, lines_consumed) =
@@ -231,8 +503,13 @@ impl Parser {
                     let content_lines = &lines[i..i + lines_consumed - 1];
                     let trimmed: Vec<&str> = content_lines.iter().map(|line| line.trim()).collect();
                     let text = trimmed.join("\n");
-                    let children = self.parse_inline(&text);
-                    blocks.push(Node::Heading { level, children });
+                    let (text, attributes) = self.split_heading_attributes(&text);
+                    let children = self.parse_inline(text);
+                    blocks.push(Node::Heading {
+                        level,
+                        children,
+                        attributes,
+                    });
                     i += lines_consumed;
                 } else {
                     // Not a Setext heading, treat as paragraph
@@ -323,6 +600,7 @@ impl Parser {
             Node::CodeBlock {
                 info: String::new(),
                 literal,
+                attributes: Attributes::default(),
             },
             i,
         )
@@ -428,6 +706,7 @@ impl Parser {
                 Node::CodeBlock {
                     info: String::new(),
                     literal: String::new(),
+                    attributes: Attributes::default(),
                 },
                 0,
             );
@@ -441,7 +720,8 @@ impl Parser {
 
         // Info string is everything after the fence, trimmed
         // But only the first word becomes the language class
-        let info_string = after_fence.trim();
+        let (info_string, attributes) =
+            self.split_trailing_attributes(after_fence.trim(), self.options.attributes);
         let info = if info_string.is_empty() {
             String::new()
         } else {
@@ -479,7 +759,19 @@ impl Parser {
             code_lines.join("\n") + "\n"
         };
 
-        (Node::CodeBlock { info, literal }, i)
+        if self.options.math && info == "math" {
+            let math = literal.trim_end_matches('\n').to_string();
+            return (Node::MathBlock(math), i);
+        }
+
+        (
+            Node::CodeBlock {
+                info,
+                literal,
+                attributes,
+            },
+            i,
+        )
     }
 
     /// Check if a line is a valid closing fence
@@ -565,11 +857,13 @@ impl Parser {
             text = "";
         }
 
+        let (text, attributes) = self.split_heading_attributes(text);
         let children = self.parse_inline(text);
 
         Some(Node::Heading {
             level: hash_count as u8,
             children,
+            attributes,
         })
     }
 
@@ -706,9 +1000,26 @@ impl Parser {
             }
         }
 
+        // A GitHub alert marker is only recognized alone on the first line
+        let alert = if self.options.alerts {
+            quote_lines
+                .first()
+                .and_then(|line| Self::alert_marker(line))
+        } else {
+            None
+        };
+        if let Some(kind) = alert {
+            let content = quote_lines[1..].join("\n");
+            if let Node::Document(children) = self.parse_blocks(&content)
+                && !children.is_empty()
+            {
+                return (Node::Alert { kind, children }, i);
+            }
+        }
+
         // Parse the collected lines recursively
         let content = quote_lines.join("\n");
-        let inner_ast = self.parse(&content);
+        let inner_ast = self.parse_blocks(&content);
 
         // Extract children from the Document node
         let children = match inner_ast {
@@ -719,6 +1030,91 @@ impl Parser {
         (Node::BlockQuote(children), i)
     }
 
+    /// Check if a line opens a `::: name` container, returning the number of colons
+    fn is_container_start(&self, line: &str) -> Option<usize> {
+        if !self.options.containers {
+            return None;
+        }
+        let indent = self.count_leading_spaces(line);
+        if indent >= 4 {
+            return None;
+        }
+
+        // At least three colons followed by a name
+        let after_indent = &line[indent..];
+        let colons = after_indent.chars().take_while(|&c| c == ':').count();
+        if colons < 3 || after_indent[colons..].trim().is_empty() {
+            return None;
+        }
+        Some(colons)
+    }
+
+    /// Check if a line closes a container opened with `min_colons` colons
+    fn is_container_end(&self, line: &str, min_colons: usize) -> bool {
+        if self.count_leading_spaces(line) >= 4 {
+            return false;
+        }
+        let trimmed = line.trim();
+        trimmed.len() >= min_colons && trimmed.chars().all(|c| c == ':')
+    }
+
+    /// Parse a fenced container; an unclosed container runs to the end of the input
+    fn parse_container(&mut self, lines: &[&str], colons: usize) -> (Node, usize) {
+        let indent = self.count_leading_spaces(lines[0]);
+        let info = lines[0][indent + colons..].trim();
+        let (name, args) = match info.split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args.trim()),
+            None => (info, ""),
+        };
+
+        // A closing fence inside fenced code is code, not the end of the container
+        let mut content_lines = Vec::new();
+        let mut fence = None;
+        let mut i = 1;
+        while i < lines.len() {
+            let line = lines[i];
+            i += 1;
+            match fence {
+                Some((fence_char, fence_len)) => {
+                    if self.is_closing_fence(line, fence_char, fence_len) {
+                        fence = None;
+                    }
+                }
+                None => {
+                    if self.is_container_end(line, colons) {
+                        break;
+                    }
+                    if let Some((fence_char, fence_len, _)) = self.is_fenced_code_start(line) {
+                        fence = Some((fence_char, fence_len));
+                    }
+                }
+            }
+            content_lines.push(line);
+        }
+
+        // Parse the content recursively, like a blockquote
+        let children = match self.parse_blocks(&content_lines.join("\n")) {
+            Node::Document(children) => children,
+            other => vec![other],
+        };
+
+        (
+            Node::Container {
+                name: name.to_string(),
+                args: args.to_string(),
+                children,
+            },
+            i,
+        )
+    }
+
+    /// Alert kind for a `[!KIND]` line (GitHub ignores alerts without content, so the
+    /// caller still has to check there is some)
+    fn alert_marker(line: &str) -> Option<AlertKind> {
+        let name = line.trim().strip_prefix("[!")?.strip_suffix(']')?;
+        AlertKind::from_name(name)
+    }
+
     /// Strip the blockquote marker (>) and optional following space from a line
     fn strip_blockquote_marker(&self, line: &str) -> String {
         // Remove leading spaces (up to 3)
@@ -1582,7 +1978,7 @@ impl Parser {
             result
         };
 
-        let parsed = self.parse(&item_content);
+        let parsed = self.parse_blocks(&item_content);
 
         // Extract children from the parsed document
         let children = match parsed {
@@ -1607,9 +2003,13 @@ impl Parser {
                     c,
                     Node::Paragraph(_)
                         | Node::CodeBlock { .. }
+                        | Node::MathBlock(_)
                         | Node::BlockQuote(_)
+                        | Node::Alert { .. }
+                        | Node::Container { .. }
                         | Node::UnorderedList { .. }
                         | Node::OrderedList { .. }
+                        | Node::DefinitionList(_)
                         | Node::Heading { .. }
                         | Node::ThematicBreak
                         | Node::HtmlBlock(_)
@@ -1941,6 +2341,152 @@ impl Parser {
             }
         }
     }
+
+    /// Check if a line starts a definition: `:` and a space or tab after up to 3 spaces
+    fn is_definition_marker(&self, line: &str) -> bool {
+        let indent = self.count_leading_spaces(line);
+        if indent > 3 {
+            return false;
+        }
+        let rest = &line[indent..];
+        rest.starts_with(": ") || rest.starts_with(":\t")
+    }
+
+    /// Check if a definition list starts here (only when the extension is enabled)
+    fn is_definition_list_start(&self, lines: &[&str]) -> bool {
+        self.options.definition_lists && self.definition_terms_len(lines).is_some()
+    }
+
+    /// If `lines` starts with term lines followed by a definition marker (optionally after
+    /// one blank line), return the number of term lines and of blank lines after them
+    fn definition_terms_len(&self, lines: &[&str]) -> Option<(usize, usize)> {
+        let mut i = 0;
+        while i < lines.len() && !lines[i].trim().is_empty() && !self.is_definition_marker(lines[i])
+        {
+            // Terms are plain paragraph-like lines
+            if self.is_block_structure_start(lines[i]) || self.is_indented_code_line(lines[i]) {
+                return None;
+            }
+            i += 1;
+        }
+        if i == 0 {
+            return None;
+        }
+
+        let term_count = i;
+        if i < lines.len() && lines[i].trim().is_empty() {
+            i += 1;
+        }
+        if i < lines.len() && self.is_definition_marker(lines[i]) {
+            Some((term_count, i - term_count))
+        } else {
+            None
+        }
+    }
+
+    /// Parse a definition list: groups of terms, each followed by one or more definitions
+    /// Each term line is a separate term. A blank line before a definition makes it loose.
+    fn parse_definition_list(&mut self, lines: &[&str]) -> (Node, usize) {
+        let mut children = Vec::new();
+        let mut i = 0;
+
+        while let Some((term_count, blank_count)) = self.definition_terms_len(&lines[i..]) {
+            for term in &lines[i..i + term_count] {
+                children.push(Node::DefinitionTerm(self.parse_inline(term.trim())));
+            }
+            i += term_count + blank_count;
+
+            let mut blank_before = blank_count > 0;
+            loop {
+                let (details, lines_consumed) =
+                    self.parse_definition_details(&lines[i..], blank_before);
+                children.push(details);
+                i += lines_consumed;
+
+                // Blank lines may separate definitions of the same terms
+                let next = self.skip_blank_lines(lines, i);
+                if next < lines.len() && self.is_definition_marker(lines[next]) {
+                    blank_before = next > i;
+                    i = next;
+                } else {
+                    break;
+                }
+            }
+
+            // Continue with the next group of terms, if any
+            let next = self.skip_blank_lines(lines, i);
+            if next < lines.len() && self.definition_terms_len(&lines[next..]).is_some() {
+                i = next;
+            } else {
+                break;
+            }
+        }
+
+        (Node::DefinitionList(children), i)
+    }
+
+    /// Parse one definition starting at its `:` marker line
+    /// Continuation lines are indented 4+ columns, or lazy paragraph continuations.
+    fn parse_definition_details(&mut self, lines: &[&str], blank_before: bool) -> (Node, usize) {
+        let indent = self.count_leading_spaces(lines[0]);
+        let mut content_lines = vec![lines[0][indent + 1..].trim_start().to_string()];
+        let mut has_blank = false;
+        let mut i = 1;
+
+        while i < lines.len() {
+            let line = lines[i];
+
+            if line.trim().is_empty() {
+                // Blank lines belong to the definition only if indented content follows
+                let next = self.skip_blank_lines(lines, i);
+                if next < lines.len() && self.count_indent_columns(lines[next]) >= 4 {
+                    content_lines.extend(std::iter::repeat_n(String::new(), next - i));
+                    has_blank = true;
+                    i = next;
+                    continue;
+                }
+                break;
+            }
+
+            if self.count_indent_columns(line) >= 4 {
+                content_lines.push(self.remove_indent_columns(line, 4));
+                i += 1;
+                continue;
+            }
+
+            // Lazy continuation, unless the line starts something else
+            // (a single line directly followed by a marker is the next term; only the
+            // next line needs checking, as this one is already known to be term-like)
+            let after_blank = content_lines.last().is_some_and(|l| l.trim().is_empty());
+            if after_blank
+                || self.is_definition_marker(line)
+                || self.is_block_structure_start(line)
+                || lines
+                    .get(i + 1)
+                    .is_some_and(|next| self.is_definition_marker(next))
+            {
+                break;
+            }
+            content_lines.push(line.to_string());
+            i += 1;
+        }
+
+        let children = match self.parse_blocks(&content_lines.join("\n")) {
+            Node::Document(children) => children,
+            other => vec![other],
+        };
+        let tight = !blank_before && !has_blank;
+        (Node::DefinitionDetails { tight, children }, i)
+    }
+
+    /// Index of the first non-blank line at or after `start`
+    fn skip_blank_lines(&self, lines: &[&str], start: usize) -> usize {
+        let mut i = start;
+        while i < lines.len() && lines[i].trim().is_empty() {
+            i += 1;
+        }
+        i
+    }
 }
 
 /// List type identifier
@@ -1974,6 +2520,204 @@ fn count_leading_spaces(line: &str) -> usize {
     count
 }
 
+/// URL-encode a string for use in href attributes (percent-encode non-ASCII and special chars)
+/// Preserves already percent-encoded sequences
+pub(crate) fn url_encode(text: &str) -> String {
+    let mut result = String::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+
+        // Check if this is an already percent-encoded sequence (%HH)
+        if ch == '%'
+            && i + 2 < chars.len()
+            && chars[i + 1].is_ascii_hexdigit()
+            && chars[i + 2].is_ascii_hexdigit()
+        {
+            // Preserve existing percent-encoding
+            result.push('%');
+            result.push(chars[i + 1]);
+            result.push(chars[i + 2]);
+            i += 3;
+            continue;
+        }
+
+        // ASCII alphanumeric and safe URL characters pass through
+        if ch.is_ascii_alphanumeric()
+            || matches!(
+                ch,
+                '-' | '_'
+                    | '.'
+                    | '~'
+                    | '!'
+                    | '*'
+                    | '\''
+                    | '('
+                    | ')'
+                    | ';'
+                    | ':'
+                    | '@'
+                    | '&'
+                    | '='
+                    | '+'
+                    | '$'
+                    | ','
+                    | '/'
+                    | '?'
+                    | '#'
+                    | '['
+                    | ']'
+            )
+        {
+            result.push(ch);
+            i += 1;
+        } else {
+            // Percent-encode as UTF-8 bytes
+            for byte in ch.to_string().as_bytes() {
+                result.push_str(&format!("%{:02X}", byte));
+            }
+            i += 1;
+        }
+    }
+
+    result
+}
+
+/// Give headings without an id one generated from their text. Generated ids never
+/// repeat an explicit id or each other.
+fn assign_heading_ids(document: &mut Node) {
+    let mut used = HashSet::new();
+    for_each_heading(document, &mut |attributes, _| {
+        if let Some(id) = &attributes.id {
+            used.insert(id.clone());
+        }
+    });
+
+    // Last suffix tried for each slug, so repeated headings don't rescan from -1
+    let mut suffixes: HashMap<String, usize> = HashMap::new();
+
+    for_each_heading(document, &mut |attributes, children| {
+        if attributes.id.is_some() {
+            return;
+        }
+        let slug = slugify(&alt_text_to_string(children));
+        if slug.is_empty() {
+            return;
+        }
+
+        let suffix = suffixes.entry(slug.clone()).or_insert(0);
+        let mut id = slug.clone();
+        while used.contains(&id) {
+            *suffix += 1;
+            id = format!("{}-{}", slug, suffix);
+        }
+        used.insert(id.clone());
+        attributes.id = Some(id);
+    });
+}
+
+/// Call `f` with the attributes and children of every heading, in document order
+fn for_each_heading(node: &mut Node, f: &mut dyn FnMut(&mut Attributes, &[Node])) {
+    match node {
+        Node::Heading {
+            children,
+            attributes,
+            ..
+        } => f(attributes, children),
+        Node::Document(children)
+        | Node::BlockQuote(children)
+        | Node::DefinitionList(children)
+        | Node::Alert { children, .. }
+        | Node::Container { children, .. }
+        | Node::UnorderedList { children, .. }
+        | Node::OrderedList { children, .. }
+        | Node::ListItem { children, .. }
+        | Node::DefinitionDetails { children, .. } => {
+            for child in children {
+                for_each_heading(child, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// GitHub-style anchor slug: lowercase, punctuation removed, spaces as hyphens
+fn slugify(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Replace the text of inline nodes with `f` applied to it, merging adjacent text nodes
+/// first. Code, links and images are left alone.
+fn map_text(nodes: Vec<Node>, f: &dyn Fn(&str) -> Vec<Node>) -> Vec<Node> {
+    let mut result = Vec::new();
+    let mut text = String::new();
+
+    for node in nodes {
+        if let Node::Text(t) = node {
+            text.push_str(&t);
+            continue;
+        }
+        if !text.is_empty() {
+            result.extend(f(&text));
+            text.clear();
+        }
+        result.push(match node {
+            Node::Emphasis(children) => Node::Emphasis(map_text(children, f)),
+            Node::Strong(children) => Node::Strong(map_text(children, f)),
+            Node::Superscript(children) => Node::Superscript(map_text(children, f)),
+            Node::Subscript(children) => Node::Subscript(map_text(children, f)),
+            Node::Mark(children) => Node::Mark(map_text(children, f)),
+            Node::Insert(children) => Node::Insert(map_text(children, f)),
+            Node::Span {
+                children,
+                attributes,
+            } => Node::Span {
+                children: map_text(children, f),
+                attributes,
+            },
+            other => other,
+        });
+    }
+    if !text.is_empty() {
+        result.extend(f(&text));
+    }
+    result
+}
+
+fn is_reference_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '@' | '#')
+}
+
+/// Convert a run of 2+ hyphens to dashes the way cmark does: all em dashes if the
+/// count is a multiple of 3, else all en dashes if even, else em dashes followed by
+/// one or two en dashes
+fn smart_dashes(count: usize) -> String {
+    let (em_count, en_count) = if count.is_multiple_of(3) {
+        (count / 3, 0)
+    } else if count.is_multiple_of(2) {
+        (0, count / 2)
+    } else if count % 3 == 2 {
+        ((count - 2) / 3, 1)
+    } else {
+        ((count - 4) / 3, 2)
+    };
+
+    let mut dashes = EM_DASH.to_string().repeat(em_count);
+    dashes.push_str(&EN_DASH.to_string().repeat(en_count));
+    dashes
+}
+
 impl Default for Parser {
     fn default() -> Self {
         Self::new()
@@ -2010,6 +2754,16 @@ impl Parser {
                 break;
             }
 
+            // Stop on fenced container
+            if self.is_container_start(line).is_some() {
+                break;
+            }
+
+            // Stop on abbreviation definition
+            if self.parse_abbreviation_definition(line).is_some() {
+                break;
+            }
+
             // Stop on indented code block (4+ spaces) - but ONLY on the first line
             // Per CommonMark spec: "Lines after the first may be indented any amount,
             // since indented code blocks cannot interrupt paragraphs."
@@ -2084,18 +2838,95 @@ impl Parser {
     /// Uses a delimiter-based approach for emphasis per CommonMark spec
     fn parse_inline(&self, text: &str) -> Vec<Node> {
         let chars: Vec<char> = text.chars().collect();
-        self.parse_inline_with_delimiter_stack(&chars, 0, chars.len(), false)
+        let mut nodes = self.parse_inline_with_delimiter_stack(&chars, 0, chars.len(), false);
+        if !self.abbreviations.is_empty() {
+            nodes = map_text(nodes, &|text| self.split_abbreviations(text));
+        }
+        nodes
     }
 
-    /// Parse inline elements inside a link (links cannot contain other links)
-    fn parse_inline_in_link(&self, text: &str) -> Vec<Node> {
-        let chars: Vec<char> = text.chars().collect();
-        self.parse_inline_with_delimiter_stack(&chars, 0, chars.len(), true)
+    /// Parse an abbreviation definition line `*[label]: title` into `(label, title)`
+    fn parse_abbreviation_definition<'a>(&self, line: &'a str) -> Option<(&'a str, &'a str)> {
+        if !self.options.abbreviations || self.count_leading_spaces(line) >= 4 {
+            return None;
+        }
+        let rest = line.trim_start().strip_prefix("*[")?;
+        let (label, title) = rest.split_once("]:")?;
+        if label.trim().is_empty() || label.contains(['[', ']']) {
+            return None;
+        }
+        Some((label.trim(), title.trim()))
     }
 
-    /// Parse inline elements with proper delimiter stack algorithm per CommonMark spec
-    fn parse_inline_with_delimiter_stack(
-        &self,
+    /// Store an abbreviation definition if `line` is one (the first definition of a
+    /// label wins)
+    fn try_parse_abbreviation_definition(&mut self, line: &str) -> bool {
+        match self.parse_abbreviation_definition(line) {
+            Some((label, title)) => {
+                self.abbreviations
+                    .entry(label.to_string())
+                    .or_insert_with(|| title.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Split defined abbreviations that appear as whole words out of plain text, preferring
+    /// the longest one at each position
+    fn split_abbreviations(&self, text: &str) -> Vec<Node> {
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let mut nodes = Vec::new();
+        let mut plain_start = 0;
+        let mut i = 0;
+
+        while let Some(c) = text[i..].chars().next() {
+            let at_boundary = !text[..i].chars().next_back().is_some_and(is_word_char);
+            let found = if at_boundary {
+                self.abbreviations
+                    .iter()
+                    .filter(|(abbreviation, _)| {
+                        text[i..].starts_with(abbreviation.as_str())
+                            && !text[i + abbreviation.len()..]
+                                .chars()
+                                .next()
+                                .is_some_and(is_word_char)
+                    })
+                    .max_by_key(|(abbreviation, _)| abbreviation.len())
+            } else {
+                None
+            };
+
+            match found {
+                Some((abbreviation, title)) => {
+                    if plain_start < i {
+                        nodes.push(Node::Text(text[plain_start..i].to_string()));
+                    }
+                    nodes.push(Node::Abbreviation {
+                        text: abbreviation.clone(),
+                        title: title.clone(),
+                    });
+                    i += abbreviation.len();
+                    plain_start = i;
+                }
+                None => i += c.len_utf8(),
+            }
+        }
+        if plain_start < text.len() {
+            nodes.push(Node::Text(text[plain_start..].to_string()));
+        }
+        nodes
+    }
+
+    /// Parse inline elements inside a link (links cannot contain other links)
+    fn parse_inline_in_link(&self, text: &str) -> Vec<Node> {
+        let chars: Vec<char> = text.chars().collect();
+        self.parse_inline_with_delimiter_stack(&chars, 0, chars.len(), true)
+    }
+
+    /// Parse inline elements with proper delimiter stack algorithm per CommonMark spec
+    fn parse_inline_with_delimiter_stack(
+        &self,
         chars: &[char],
         start: usize,
         end: usize,
@@ -2103,6 +2934,7 @@ impl Parser {
     ) -> Vec<Node> {
         let mut nodes = Vec::new();
         let mut delimiter_stack: Vec<DelimiterRun> = Vec::new();
+        let mut unclosed_math = [false; 2];
         let mut i = start;
 
         // First pass: collect all inline elements and delimiter runs
@@ -2157,6 +2989,44 @@ impl Parser {
                 }
             }
 
+            // Try to parse math; like code spans, its content is taken verbatim
+            if self.options.math && chars[i] == '$' {
+                if let Some((math_node, new_i)) =
+                    self.try_parse_math(chars, i, end, &mut unclosed_math)
+                {
+                    nodes.push(math_node);
+                    i = new_i;
+                } else {
+                    // Consume the whole dollar run so `$$` isn't retried as inline math
+                    let start = i;
+                    while i < end && chars[i] == '$' {
+                        i += 1;
+                    }
+                    nodes.push(Node::Text(chars[start..i].iter().collect()));
+                }
+                continue;
+            }
+
+            // Emoji shortcodes; failed attempts leave the colon as text
+            if self.options.emoji
+                && chars[i] == ':'
+                && let Some((emoji_node, new_i)) = self.try_parse_emoji(chars, i, end)
+            {
+                nodes.push(emoji_node);
+                i = new_i;
+                continue;
+            }
+
+            // Mentions and references, but not inside link text
+            if self.options.mentions
+                && !inside_link
+                && let Some((reference_node, new_i)) = self.try_parse_reference(chars, i, end)
+            {
+                nodes.push(reference_node);
+                i = new_i;
+                continue;
+            }
+
             // Try to parse autolink (before regular links)
             if chars[i] == '<'
                 && let Some((autolink_node, new_i)) = self.try_parse_autolink(chars, i)
@@ -2179,24 +3049,47 @@ impl Parser {
             if chars[i] == '!'
                 && i + 1 < end
                 && chars[i + 1] == '['
-                && let Some((image_node, new_i)) = self.try_parse_image(chars, i)
+                && let Some(image) = self.try_parse_image(chars, i)
             {
+                let (image_node, new_i) = self.with_trailing_attributes(chars, image, end);
                 nodes.push(image_node);
                 i = new_i;
                 continue;
             }
 
+            // Wiki links take precedence over regular links
+            if self.options.wiki_links
+                && !inside_link
+                && chars[i] == '['
+                && let Some((wiki_link, new_i)) = self.try_parse_wiki_link(chars, i, end)
+            {
+                nodes.push(wiki_link);
+                i = new_i;
+                continue;
+            }
+
             // Try to parse link (links take precedence over emphasis per Rule 17)
             // Links cannot contain other links per CommonMark spec
             if !inside_link
                 && chars[i] == '['
-                && let Some((link_node, new_i)) = self.try_parse_link(chars, i)
+                && let Some(link) = self.try_parse_link(chars, i)
             {
+                let (link_node, new_i) = self.with_trailing_attributes(chars, link, end);
                 nodes.push(link_node);
                 i = new_i;
                 continue;
             }
 
+            // Brackets that don't form a link may still be a span with attributes
+            if self.options.attributes
+                && chars[i] == '['
+                && let Some((span_node, new_i)) = self.try_parse_span(chars, i, end, inside_link)
+            {
+                nodes.push(span_node);
+                i = new_i;
+                continue;
+            }
+
             // Handle emphasis delimiters - add to stack
             if chars[i] == '*' || chars[i] == '_' {
                 let delimiter = chars[i];
@@ -2252,6 +3145,90 @@ impl Parser {
                 continue;
             }
 
+            // Other registered delimiters use the flanking rules of `*`
+            if let Some(kind) = self.inline_delimiter(chars[i]) {
+                let delim_start = i;
+                while i < end && chars[i] == kind.delimiter {
+                    i += 1;
+                }
+                let count = i - delim_start;
+                nodes.push(Node::Text(chars[delim_start..i].iter().collect()));
+
+                if count == kind.length {
+                    let can_open = self.is_left_flanking(chars, delim_start, count);
+                    let can_close = self.is_right_flanking(chars, delim_start, count);
+                    if can_open || can_close {
+                        delimiter_stack.push(DelimiterRun {
+                            delimiter: kind.delimiter,
+                            count,
+                            pos: nodes.len() - 1,
+                            can_open,
+                            can_close,
+                            active: true,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            // Smart punctuation: quotes go on the delimiter stack like emphasis delimiters
+            // and are paired in process_emphasis
+            if self.options.smart && (chars[i] == '\'' || chars[i] == '"') {
+                let delimiter = chars[i];
+                let is_left_flanking = self.is_left_flanking(chars, i, 1);
+                let is_right_flanking = self.is_right_flanking(chars, i, 1);
+                let before_char = if i == 0 { ' ' } else { chars[i - 1] };
+
+                let can_open = is_left_flanking
+                    && !is_right_flanking
+                    && before_char != ']'
+                    && before_char != ')';
+                let can_close = is_right_flanking;
+
+                // Unmatched quotes keep this form
+                let quote = match delimiter {
+                    '\'' => RIGHT_SINGLE_QUOTE,
+                    _ if can_close => RIGHT_DOUBLE_QUOTE,
+                    _ => LEFT_DOUBLE_QUOTE,
+                };
+                nodes.push(Node::Text(quote.to_string()));
+                i += 1;
+
+                if can_open || can_close {
+                    delimiter_stack.push(DelimiterRun {
+                        delimiter,
+                        count: 1,
+                        pos: nodes.len() - 1,
+                        can_open,
+                        can_close,
+                        active: true,
+                    });
+                }
+                continue;
+            }
+
+            // Smart punctuation: runs of 2+ hyphens become en/em dashes
+            if self.options.smart && chars[i] == '-' && i + 1 < end && chars[i + 1] == '-' {
+                let dash_start = i;
+                while i < end && chars[i] == '-' {
+                    i += 1;
+                }
+                nodes.push(Node::Text(smart_dashes(i - dash_start)));
+                continue;
+            }
+
+            // Smart punctuation: three periods become an ellipsis
+            if self.options.smart
+                && chars[i] == '.'
+                && i + 2 < end
+                && chars[i + 1] == '.'
+                && chars[i + 2] == '.'
+            {
+                nodes.push(Node::Text(ELLIPSIS.to_string()));
+                i += 3;
+                continue;
+            }
+
             // Collect regular text until next special character
             let text_start = i;
             while i < end
@@ -2264,6 +3241,13 @@ impl Parser {
                 && chars[i] != '!'
                 && chars[i] != '<'
                 && chars[i] != '\n'
+                && !(self.options.smart && self.is_smart_punctuation_start(chars, i, end))
+                && !(self.options.math && chars[i] == '$')
+                && self.inline_delimiter(chars[i]).is_none()
+                && !(self.options.emoji && chars[i] == ':')
+                && !(self.options.mentions
+                    && !inside_link
+                    && self.try_parse_reference(chars, i, end).is_some())
             {
                 i += 1;
             }
@@ -2367,16 +3351,41 @@ impl Parser {
                 break;
             }
 
+            // Smart quotes: the closer always becomes a right quote, a matching opener a
+            // left quote; delimiters between them stay on the stack
+            let delimiter = delimiter_stack[closer_idx].delimiter;
+            if delimiter == '\'' || delimiter == '"' {
+                let (left, right) = if delimiter == '\'' {
+                    (LEFT_SINGLE_QUOTE, RIGHT_SINGLE_QUOTE)
+                } else {
+                    (LEFT_DOUBLE_QUOTE, RIGHT_DOUBLE_QUOTE)
+                };
+                nodes[delimiter_stack[closer_idx].pos] = Node::Text(right.to_string());
+
+                if let Some(opener_idx) = found_opener {
+                    nodes[delimiter_stack[opener_idx].pos] = Node::Text(left.to_string());
+                    delimiter_stack.remove(closer_idx);
+                    delimiter_stack.remove(opener_idx);
+                    // The opener was below the closer, so the next closer shifts down by one
+                    closer_idx -= 1;
+                } else {
+                    closer_idx += 1;
+                }
+                continue;
+            }
+
             if let Some(opener_idx) = found_opener {
-                // Determine how many delimiters to use (prefer 2 for strong, else 1 for em)
+                // Determine how many delimiters to use: registered delimiters use their whole
+                // run, emphasis prefers 2 for strong, else 1 for em
                 let opener_count = delimiter_stack[opener_idx].count;
                 let closer_count = delimiter_stack[closer_idx].count;
 
-                let use_delims = if opener_count >= 2 && closer_count >= 2 {
-                    2 // strong
-                } else {
-                    1 // emphasis
-                };
+                let (use_delims, wrap): (usize, fn(Vec<Node>) -> Node) =
+                    match self.inline_delimiter(delimiter) {
+                        Some(kind) => (kind.length, kind.node),
+                        None if opener_count >= 2 && closer_count >= 2 => (2, Node::Strong),
+                        None => (1, Node::Emphasis),
+                    };
 
                 // Extract content between opener and closer
                 let opener_pos = delimiter_stack[opener_idx].pos;
@@ -2385,7 +3394,7 @@ impl Parser {
                 let closer_count = delimiter_stack[closer_idx].count;
 
                 // Remove delimiters from the text nodes and create emphasis node
-                let new_node = self.create_emphasis_node(nodes, opener_pos, closer_pos, use_delims);
+                let new_node = self.create_emphasis_node(nodes, opener_pos, closer_pos, wrap);
 
                 // Replace the range with the new emphasis node
                 // This updates nodes and adjusts positions
@@ -2435,13 +3444,14 @@ impl Parser {
         }
     }
 
-    /// Create an emphasis or strong node from the content between two positions
+    /// Create an emphasis, strong or other delimited node from the content between two
+    /// positions
     fn create_emphasis_node(
         &self,
         nodes: &[Node],
         opener_pos: usize,
         closer_pos: usize,
-        use_delims: usize,
+        wrap: fn(Vec<Node>) -> Node,
     ) -> Node {
         // Extract content between delimiters (excluding the delimiter text nodes themselves)
         let mut content = Vec::new();
@@ -2449,11 +3459,14 @@ impl Parser {
             content.push(node.clone());
         }
 
-        if use_delims == 2 {
-            Node::Strong(content)
-        } else {
-            Node::Emphasis(content)
-        }
+        wrap(content)
+    }
+
+    /// The enabled registered delimiter for `c`, if any
+    fn inline_delimiter(&self, c: char) -> Option<&'static InlineDelimiter> {
+        INLINE_DELIMITERS
+            .iter()
+            .find(|kind| kind.delimiter == c && (kind.enabled)(&self.options))
     }
 
     /// Replace the delimiter range with an emphasis node
@@ -2505,6 +3518,16 @@ impl Parser {
         }
     }
 
+    /// Check if smart punctuation handling starts at `pos` (a quote, `--` or `...`)
+    fn is_smart_punctuation_start(&self, chars: &[char], pos: usize, end: usize) -> bool {
+        match chars[pos] {
+            '\'' | '"' => true,
+            '-' => pos + 1 < end && chars[pos + 1] == '-',
+            '.' => pos + 2 < end && chars[pos + 1] == '.' && chars[pos + 2] == '.',
+            _ => false,
+        }
+    }
+
     /// Check if a character is ASCII punctuation (can be backslash-escaped)
     fn is_ascii_punctuation(&self, ch: char) -> bool {
         matches!(
@@ -2584,69 +3607,55 @@ impl Parser {
         result
     }
 
-    /// URL-encode a string for use in href attributes (percent-encode non-ASCII and special chars)
-    /// Preserves already percent-encoded sequences
-    fn url_encode(&self, text: &str) -> String {
-        let mut result = String::new();
-        let chars: Vec<char> = text.chars().collect();
-        let mut i = 0;
+    /// Try to parse `$$display$$` or `$inline$` math starting at `start`
+    /// Follows the commonmark-hs dollar rules: inline math can't start with whitespace,
+    /// and its closing `$` can't follow whitespace or precede a digit.
+    /// Backslashes are kept verbatim but an escaped `$` doesn't close.
+    /// `unclosed` records, for inline and display math, that a scan reached `end`
+    /// without a closer; later openers of that kind can't find one either, so they
+    /// fail without scanning again.
+    fn try_parse_math(
+        &self,
+        chars: &[char],
+        start: usize,
+        end: usize,
+        unclosed: &mut [bool; 2],
+    ) -> Option<(Node, usize)> {
+        let display = start + 1 < end && chars[start + 1] == '$';
+        let content_start = if display { start + 2 } else { start + 1 };
 
-        while i < chars.len() {
-            let ch = chars[i];
+        if !display && (content_start >= end || chars[content_start].is_whitespace()) {
+            return None;
+        }
+        if unclosed[display as usize] {
+            return None;
+        }
 
-            // Check if this is an already percent-encoded sequence (%HH)
-            if ch == '%'
-                && i + 2 < chars.len()
-                && chars[i + 1].is_ascii_hexdigit()
-                && chars[i + 2].is_ascii_hexdigit()
-            {
-                // Preserve existing percent-encoding
-                result.push('%');
-                result.push(chars[i + 1]);
-                result.push(chars[i + 2]);
-                i += 3;
+        let mut j = content_start;
+        while j < end {
+            if chars[j] == '\\' {
+                j += 2;
                 continue;
             }
-
-            // ASCII alphanumeric and safe URL characters pass through
-            if ch.is_ascii_alphanumeric()
-                || matches!(
-                    ch,
-                    '-' | '_'
-                        | '.'
-                        | '~'
-                        | '!'
-                        | '*'
-                        | '\''
-                        | '('
-                        | ')'
-                        | ';'
-                        | ':'
-                        | '@'
-                        | '&'
-                        | '='
-                        | '+'
-                        | '$'
-                        | ','
-                        | '/'
-                        | '?'
-                        | '#'
-                        | '['
-                        | ']'
-                )
-            {
-                result.push(ch);
-                i += 1;
-            } else {
-                // Percent-encode as UTF-8 bytes
-                for byte in ch.to_string().as_bytes() {
-                    result.push_str(&format!("%{:02X}", byte));
+            if chars[j] == '$' && j > content_start {
+                if display {
+                    if j + 1 < end && chars[j + 1] == '$' {
+                        let math: String = chars[content_start..j].iter().collect();
+                        return Some((Node::DisplayMath(math), j + 2));
+                    }
+                } else {
+                    let followed_by_digit = j + 1 < end && chars[j + 1].is_ascii_digit();
+                    if !chars[j - 1].is_whitespace() && !followed_by_digit {
+                        let math: String = chars[content_start..j].iter().collect();
+                        return Some((Node::InlineMath(math), j + 1));
+                    }
                 }
-                i += 1;
             }
+            j += 1;
         }
 
-        result
+        unclosed[display as usize] = true;
+        None
     }
 
     fn try_parse_code_span(&self, chars: &[char], start: usize) -> Option<(Node, usize)> {
@@ -2812,6 +3821,393 @@ impl Parser {
         after_char.is_whitespace() || self.is_unicode_punctuation(after_char)
     }
 
+    /// Parse a `[[Page#anchor|alias]]` wiki link starting at the first `[`
+    fn try_parse_wiki_link(
+        &self,
+        chars: &[char],
+        start: usize,
+        end: usize,
+    ) -> Option<(Node, usize)> {
+        if start + 1 >= end || chars[start + 1] != '[' {
+            return None;
+        }
+
+        // The target can't span lines or contain brackets; code spans bind tighter, so
+        // brackets inside one don't close the link
+        let content_start = start + 2;
+        let mut i = content_start;
+        while i < end && !matches!(chars[i], '[' | ']' | '\n') {
+            if chars[i] == '`' {
+                match self.try_parse_code_span(chars, i) {
+                    Some((_, after)) if after <= end => i = after,
+                    _ => {
+                        while i < end && chars[i] == '`' {
+                            i += 1;
+                        }
+                    }
+                }
+                continue;
+            }
+            i += 1;
+        }
+        if i + 1 >= end || chars[i] != ']' || chars[i + 1] != ']' {
+            return None;
+        }
+
+        let content: String = chars[content_start..i].iter().collect();
+        let (target, alias) = match content.split_once('|') {
+            Some((target, alias)) => (target.trim(), Some(alias.trim())),
+            None => (content.trim(), None),
+        };
+        let (page, anchor) = match target.split_once('#') {
+            Some((page, anchor)) => (page.trim(), Some(anchor.trim().to_string())),
+            None => (target, None),
+        };
+        if page.is_empty() && anchor.as_ref().is_none_or(|anchor| anchor.is_empty()) {
+            return None;
+        }
+
+        let children = match alias {
+            Some(alias) if !alias.is_empty() => self.parse_inline_in_link(alias),
+            _ => vec![Node::Text(target.to_string())],
+        };
+        Some((
+            Node::WikiLink {
+                page: page.to_string(),
+                anchor,
+                children,
+            },
+            i + 2,
+        ))
+    }
+
+    /// Parse `@user`, `#123`, `#tag` or `owner/repo#123` at `start`
+    fn try_parse_reference(
+        &self,
+        chars: &[char],
+        start: usize,
+        end: usize,
+    ) -> Option<(Node, usize)> {
+        // References only start at a word boundary, so e-mail addresses and URL
+        // fragments are left alone
+        if start > 0 && is_reference_char(chars[start - 1]) {
+            return None;
+        }
+        let chars = &chars[..end];
+        let run_end = |from: usize, allowed: fn(char) -> bool| {
+            let mut i = from;
+            while i < chars.len() && allowed(chars[i]) {
+                i += 1;
+            }
+            i
+        };
+        let is_word_char =
+            |i: usize| i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_');
+        // Issue number after a `#` at `hash`, returning the end position
+        let issue_number_end = |hash: usize| {
+            let end = run_end(hash + 1, |c| c.is_ascii_digit());
+            (end > hash + 1 && !is_word_char(end)).then_some(end)
+        };
+
+        match chars[start] {
+            '@' => {
+                // Usernames are alphanumeric with single inner hyphens
+                let mut end = run_end(start + 1, |c| c.is_ascii_alphanumeric() || c == '-');
+                while end > start + 1 && chars[end - 1] == '-' {
+                    end -= 1;
+                }
+                if end == start + 1 || chars[start + 1] == '-' || is_word_char(end) {
+                    return None;
+                }
+                let username = chars[start + 1..end].iter().collect();
+                Some((Node::Mention(username), end))
+            }
+            '#' => {
+                if let Some(end) = issue_number_end(start) {
+                    let id = chars[start + 1..end].iter().collect();
+                    let reference = Node::Reference {
+                        kind: ReferenceKind::Issue,
+                        repository: None,
+                        id,
+                    };
+                    return Some((reference, end));
+                }
+
+                // Tags start with a letter
+                if !chars.get(start + 1).is_some_and(|c| c.is_alphabetic()) {
+                    return None;
+                }
+                let mut end = run_end(start + 1, |c| c.is_alphanumeric() || c == '_' || c == '-');
+                while chars[end - 1] == '-' {
+                    end -= 1;
+                }
+                let id = chars[start + 1..end].iter().collect();
+                let reference = Node::Reference {
+                    kind: ReferenceKind::Tag,
+                    repository: None,
+                    id,
+                };
+                Some((reference, end))
+            }
+            c if c.is_ascii_alphanumeric() => {
+                let is_name_char =
+                    |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+                let owner_end = run_end(start, is_name_char);
+                if chars.get(owner_end) != Some(&'/') {
+                    return None;
+                }
+                let repo_end = run_end(owner_end + 1, is_name_char);
+                if repo_end == owner_end + 1 || chars.get(repo_end) != Some(&'#') {
+                    return None;
+                }
+                let end = issue_number_end(repo_end)?;
+                let reference = Node::Reference {
+                    kind: ReferenceKind::Issue,
+                    repository: Some(chars[start..repo_end].iter().collect()),
+                    id: chars[repo_end + 1..end].iter().collect(),
+                };
+                Some((reference, end))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse an emoji shortcode such as `:smile:` starting at the opening colon
+    fn try_parse_emoji(&self, chars: &[char], start: usize, end: usize) -> Option<(Node, usize)> {
+        // Not directly after a word character or `/`, so times, identifiers and URL
+        // paths are left alone
+        if start > 0
+            && (chars[start - 1].is_alphanumeric() || matches!(chars[start - 1], '_' | '/'))
+        {
+            return None;
+        }
+
+        let mut i = start + 1;
+        while i < end && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '_' | '+' | '-')) {
+            i += 1;
+        }
+        if i == start + 1 || i >= end || chars[i] != ':' {
+            return None;
+        }
+
+        let shortcode: String = chars[start + 1..i].iter().collect();
+        let unicode = emoji::lookup(&shortcode)?.to_string();
+        Some((Node::Emoji { shortcode, unicode }, i + 1))
+    }
+
+    /// Parse a `{#id .class key=value}` block starting at the `{` at `start`, returning
+    /// the attributes and the position after the closing `}`
+    fn try_parse_attributes(
+        &self,
+        chars: &[char],
+        start: usize,
+        end: usize,
+    ) -> Option<(Attributes, usize)> {
+        if start >= end || chars[start] != '{' {
+            return None;
+        }
+
+        let mut attributes = Attributes::default();
+        let mut i = start + 1;
+        loop {
+            while i < end && chars[i].is_whitespace() {
+                i += 1;
+            }
+            if i >= end {
+                return None;
+            }
+            if chars[i] == '}' {
+                break;
+            }
+
+            let token_start = i;
+            if chars[i] == '#' || chars[i] == '.' {
+                // #id or .class
+                i += 1;
+                while i < end && !chars[i].is_whitespace() && chars[i] != '{' && chars[i] != '}' {
+                    i += 1;
+                }
+                if i == token_start + 1 {
+                    return None;
+                }
+                let name: String = chars[token_start + 1..i].iter().collect();
+                if chars[token_start] == '#' {
+                    attributes.id = Some(name);
+                } else {
+                    attributes.classes.push(name);
+                }
+            } else {
+                // key=value, key="quoted value" or key='quoted value'
+                while i < end
+                    && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '-' | '_' | ':'))
+                {
+                    i += 1;
+                }
+                if i == token_start || i >= end || chars[i] != '=' {
+                    return None;
+                }
+                let key: String = chars[token_start..i].iter().collect();
+                // Event handler attributes would let a document run script
+                if key.len() > 2 && key[..2].eq_ignore_ascii_case("on") {
+                    return None;
+                }
+                i += 1;
+
+                let value: String = if i < end && (chars[i] == '"' || chars[i] == '\'') {
+                    let quote = chars[i];
+                    let value_start = i + 1;
+                    i = value_start;
+                    while i < end && chars[i] != quote {
+                        i += 1;
+                    }
+                    if i >= end {
+                        return None;
+                    }
+                    i += 1;
+                    chars[value_start..i - 1].iter().collect()
+                } else {
+                    let value_start = i;
+                    while i < end
+                        && !chars[i].is_whitespace()
+                        && !matches!(chars[i], '{' | '}' | '"' | '\'')
+                    {
+                        i += 1;
+                    }
+                    chars[value_start..i].iter().collect()
+                };
+
+                match key.as_str() {
+                    "id" => attributes.id = Some(value),
+                    "class" => attributes
+                        .classes
+                        .extend(value.split_whitespace().map(String::from)),
+                    _ => attributes.pairs.push((key, value)),
+                }
+            }
+
+            // Tokens are separated by whitespace
+            if i < end && !chars[i].is_whitespace() && chars[i] != '}' {
+                return None;
+            }
+        }
+
+        if attributes.is_empty() {
+            return None;
+        }
+        Some((attributes, i + 1))
+    }
+
+    /// Split trailing attribute blocks off block-level text such as a heading or an info
+    /// string. Several blocks (`{#id}{.class}`) are merged, later ones overriding the id.
+    /// Returns the text unchanged when `enabled` is false or there is no block.
+    fn split_trailing_attributes<'a>(&self, text: &'a str, enabled: bool) -> (&'a str, Attributes) {
+        let mut text = text;
+        let mut blocks = Vec::new();
+        while enabled
+            && text.ends_with('}')
+            && let Some(open) = text.rfind('{')
+            && !text[..open].ends_with('\\')
+        {
+            let chars: Vec<char> = text[open..].chars().collect();
+            match self.try_parse_attributes(&chars, 0, chars.len()) {
+                Some((attributes, end)) if end == chars.len() => {
+                    blocks.push(attributes);
+                    text = text[..open].trim_end();
+                }
+                _ => break,
+            }
+        }
+
+        let mut attributes = Attributes::default();
+        for block in blocks.into_iter().rev() {
+            if block.id.is_some() {
+                attributes.id = block.id;
+            }
+            attributes.classes.extend(block.classes);
+            attributes.pairs.extend(block.pairs);
+        }
+        (text, attributes)
+    }
+
+    /// Split a trailing `{#id .class}` block off heading text
+    fn split_heading_attributes<'a>(&self, text: &'a str) -> (&'a str, Attributes) {
+        let enabled = self.options.attributes || self.options.heading_ids;
+        self.split_trailing_attributes(text, enabled)
+    }
+
+    /// Attach an attribute block directly following a parsed link or image to it
+    fn with_trailing_attributes(
+        &self,
+        chars: &[char],
+        (mut node, i): (Node, usize),
+        end: usize,
+    ) -> (Node, usize) {
+        if !self.options.attributes {
+            return (node, i);
+        }
+        match self.try_parse_attributes(chars, i, end) {
+            Some((parsed, new_i)) => {
+                if let Node::Link { attributes, .. } | Node::Image { attributes, .. } = &mut node {
+                    *attributes = parsed;
+                }
+                (node, new_i)
+            }
+            None => (node, i),
+        }
+    }
+
+    /// Parse a bracketed span `[text]{#id .class}` starting at the `[`
+    fn try_parse_span(
+        &self,
+        chars: &[char],
+        start: usize,
+        end: usize,
+        inside_link: bool,
+    ) -> Option<(Node, usize)> {
+        // Find the matching ']', skipping backslash escapes and code spans
+        let mut depth = 0;
+        let mut i = start;
+        while i < end {
+            match chars[i] {
+                '\\' => i += 1,
+                '`' => {
+                    match self.try_parse_code_span(chars, i) {
+                        Some((_, after)) if after <= end => i = after,
+                        // An unclosed backtick run is literal text
+                        _ => {
+                            while i < end && chars[i] == '`' {
+                                i += 1;
+                            }
+                        }
+                    }
+                    continue;
+                }
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        if i >= end {
+            return None;
+        }
+
+        let (attributes, new_i) = self.try_parse_attributes(chars, i + 1, end)?;
+        let children = self.parse_inline_with_delimiter_stack(chars, start + 1, i, inside_link);
+        Some((
+            Node::Span {
+                children,
+                attributes,
+            },
+            new_i,
+        ))
+    }
+
     fn try_parse_link(&self, chars: &[char], start: usize) -> Option<(Node, usize)> {
         // Link syntax:
         // - Inline: [link text](destination "title")
@@ -2931,17 +4327,19 @@ impl Parser {
     fn contains_link(nodes: &[Node]) -> bool {
         for node in nodes {
             match node {
-                Node::Link { .. } => return true,
-                Node::Emphasis(children) | Node::Strong(children) => {
-                    if Self::contains_link(children) {
-                        return true;
-                    }
-                }
-                Node::Image { alt_text, .. } => {
-                    if Self::contains_link(alt_text) {
-                        return true;
-                    }
+                Node::Link { .. } | Node::WikiLink { .. } => return true,
+                Node::Emphasis(children)
+                | Node::Strong(children)
+                | Node::Superscript(children)
+                | Node::Subscript(children)
+                | Node::Mark(children)
+                | Node::Insert(children)
+                    if Self::contains_link(children) =>
+                {
+                    return true;
                 }
+                Node::Image { alt_text, .. } if Self::contains_link(alt_text) => return true,
+                Node::Span { children, .. } if Self::contains_link(children) => return true,
                 _ => {}
             }
         }
@@ -2985,7 +4383,7 @@ impl Parser {
             // Process backslash escapes, then entities, then URL-encode
             let escaped_dest = self.process_backslash_escapes(&raw_dest);
             let entity_decoded = self.process_entities(&escaped_dest);
-            destination = self.url_encode(&entity_decoded);
+            destination = url_encode(&entity_decoded);
             i += 1; // Move past '>'
         } else {
             // Raw destination (no spaces allowed unless in parens)
@@ -3014,7 +4412,7 @@ impl Parser {
             // Process backslash escapes, then entities, then URL-encode
             let escaped_dest = self.process_backslash_escapes(&raw_dest);
             let entity_decoded = self.process_entities(&escaped_dest);
-            destination = self.url_encode(&entity_decoded);
+            destination = url_encode(&entity_decoded);
 
             // Check for invalid characters in destination (spaces outside parens)
             if entity_decoded.contains([' ', '\t', '\n', '\r']) && paren_depth == 0 {
@@ -3072,6 +4470,7 @@ impl Parser {
                 destination,
                 title,
                 children,
+                attributes: Attributes::default(),
             },
             i,
         ))
@@ -3116,13 +4515,14 @@ impl Parser {
         i += 1; // Move past ']'
 
         // Determine the label to look up
-        let label = if raw_label.is_empty() {
+        let label_text = if raw_label.is_empty() {
             // Collapsed reference: use link text as label
-            Self::normalize_label(link_text)
+            link_text
         } else {
             // Full reference: use explicit label
-            Self::normalize_label(&raw_label)
+            raw_label.as_str()
         };
+        let label = Self::normalize_label(label_text);
 
         // Check if label is valid (must have at least one non-whitespace character)
         // Per spec: "at least one character that is not a space, tab, or line ending"
@@ -3131,13 +4531,14 @@ impl Parser {
         }
 
         // Look up the reference definition
-        if let Some((destination, title)) = self.reference_definitions.get(&label) {
+        if let Some((destination, title)) = self.resolve_reference(&label, label_text) {
             let children = self.parse_inline_in_link(link_text);
             Some((
                 Node::Link {
-                    destination: destination.clone(),
-                    title: title.clone(),
+                    destination,
+                    title,
                     children,
+                    attributes: Attributes::default(),
                 },
                 i,
             ))
@@ -3156,13 +4557,14 @@ impl Parser {
         let label = Self::normalize_label(link_text);
 
         // Look up the reference definition
-        if let Some((destination, title)) = self.reference_definitions.get(&label) {
+        if let Some((destination, title)) = self.resolve_reference(&label, link_text) {
             let children = self.parse_inline_in_link(link_text);
             Some((
                 Node::Link {
-                    destination: destination.clone(),
-                    title: title.clone(),
+                    destination,
+                    title,
                     children,
+                    attributes: Attributes::default(),
                 },
                 end_pos,
             ))
@@ -3262,7 +4664,7 @@ impl Parser {
             // Process backslash escapes, then entities, then URL-encode
             let escaped_dest = self.process_backslash_escapes(&raw_dest);
             let entity_decoded = self.process_entities(&escaped_dest);
-            destination = self.url_encode(&entity_decoded);
+            destination = url_encode(&entity_decoded);
             i += 1; // Move past '>'
         } else {
             // Raw destination (no spaces allowed unless in parens)
@@ -3291,7 +4693,7 @@ impl Parser {
             // Process backslash escapes, then entities, then URL-encode
             let escaped_dest = self.process_backslash_escapes(&raw_dest);
             let entity_decoded = self.process_entities(&escaped_dest);
-            destination = self.url_encode(&entity_decoded);
+            destination = url_encode(&entity_decoded);
 
             // Check for invalid characters in destination (spaces outside parens)
             if entity_decoded.contains([' ', '\t', '\n', '\r']) && paren_depth == 0 {
@@ -3349,6 +4751,7 @@ impl Parser {
                 destination,
                 title,
                 alt_text,
+                attributes: Attributes::default(),
             },
             i,
         ))
@@ -3381,22 +4784,24 @@ impl Parser {
         i += 1; // Move past ']'
 
         // Determine the label to look up
-        let label = if raw_label.is_empty() {
+        let label_text = if raw_label.is_empty() {
             // Collapsed reference: use alt text as label
-            Self::normalize_label(alt_text_str)
+            alt_text_str
         } else {
             // Full reference: use explicit label
-            Self::normalize_label(&raw_label)
+            raw_label.as_str()
         };
+        let label = Self::normalize_label(label_text);
 
         // Look up the reference definition
-        if let Some((destination, title)) = self.reference_definitions.get(&label) {
+        if let Some((destination, title)) = self.resolve_reference(&label, label_text) {
             let alt_text = self.parse_inline(alt_text_str);
             Some((
                 Node::Image {
-                    destination: destination.clone(),
-                    title: title.clone(),
+                    destination,
+                    title,
                     alt_text,
+                    attributes: Attributes::default(),
                 },
                 i,
             ))
@@ -3415,13 +4820,14 @@ impl Parser {
         let label = Self::normalize_label(alt_text_str);
 
         // Look up the reference definition
-        if let Some((destination, title)) = self.reference_definitions.get(&label) {
+        if let Some((destination, title)) = self.resolve_reference(&label, alt_text_str) {
             let alt_text = self.parse_inline(alt_text_str);
             Some((
                 Node::Image {
-                    destination: destination.clone(),
-                    title: title.clone(),
+                    destination,
+                    title,
                     alt_text,
+                    attributes: Attributes::default(),
                 },
                 end_pos,
             ))
@@ -3469,6 +4875,7 @@ impl Parser {
                     destination,
                     title: None,
                     children: vec![Node::Text(content)],
+                    attributes: Attributes::default(),
                 },
                 i,
             ));
@@ -3483,6 +4890,7 @@ impl Parser {
                     destination,
                     title: None,
                     children: vec![Node::Text(content)],
+                    attributes: Attributes::default(),
                 },
                 i,
             ));
@@ -3736,6 +5144,27 @@ impl Parser {
     /// Try to parse a link reference definition
     /// Returns Some(lines_consumed) if successful, None otherwise
     fn try_parse_link_reference_definition(&mut self, lines: &[&str]) -> Option<usize> {
+        let (definition, lines_consumed) = self.parse_link_reference_definition(lines)?;
+
+        // Successfully parsed - store the definition (first one wins)
+        if let Node::LinkReferenceDefinition {
+            label,
+            destination,
+            title,
+        } = definition
+        {
+            let destination = url_encode(&destination);
+            self.reference_definitions
+                .entry(Self::normalize_label(&label))
+                .or_insert((destination, title));
+        }
+
+        Some(lines_consumed)
+    }
+
+    /// Parse a link reference definition without storing it
+    /// Returns the `LinkReferenceDefinition` node (label as written) and lines consumed
+    fn parse_link_reference_definition(&self, lines: &[&str]) -> Option<(Node, usize)> {
         if lines.is_empty() {
             return None;
         }
@@ -3894,12 +5323,14 @@ impl Parser {
             None
         };
 
-        // Successfully parsed - store the definition (first one wins)
-        self.reference_definitions
-            .entry(label.clone())
-            .or_insert((destination.clone(), title.clone()));
-
-        Some(current_line + 1)
+        Some((
+            Node::LinkReferenceDefinition {
+                label: label_text,
+                destination,
+                title,
+            },
+            current_line + 1,
+        ))
     }
 
     /// Normalize a label for matching (case-insensitive, collapse whitespace)
@@ -3914,7 +5345,8 @@ impl Parser {
     }
 
     /// Parse a link destination (for reference definitions)
-    /// Returns (destination, byte_offset) or None
+    /// Returns (destination, byte_offset) or None; the destination has escapes and
+    /// entities resolved but is not yet percent-encoded
     fn parse_link_destination(&self, text: &str) -> Option<(String, usize)> {
         if text.is_empty() {
             return None;
@@ -3930,12 +5362,8 @@ impl Parser {
             while i < chars.len() {
                 match chars[i] {
                     '>' => {
-                        // Process entities and URL-encode
-                        let entity_decoded = self.process_entities(&dest);
-                        let url_encoded = self.url_encode(&entity_decoded);
-                        // Calculate byte offset
                         let byte_offset = chars[..=i].iter().map(|c| c.len_utf8()).sum();
-                        return Some((url_encoded, byte_offset));
+                        return Some((self.process_entities(&dest), byte_offset));
                     }
                     '\\' if i + 1 < chars.len() && self.is_ascii_punctuation(chars[i + 1]) => {
                         // Backslash escape of ASCII punctuation
@@ -3998,12 +5426,8 @@ impl Parser {
         if dest.is_empty() {
             None
         } else {
-            // Process entities and URL-encode
-            let entity_decoded = self.process_entities(&dest);
-            let url_encoded = self.url_encode(&entity_decoded);
-            // Calculate byte offset
             let byte_offset = chars[..i].iter().map(|c| c.len_utf8()).sum();
-            Some((url_encoded, byte_offset))
+            Some((self.process_entities(&dest), byte_offset))
         }
     }
 
@@ -4434,8 +5858,9 @@ impl Parser {
             return false;
         }
 
-        // Check if second line is a valid delimiter row
+        // Check if second line is a valid delimiter row, with one cell per header cell
         self.is_table_delimiter_row(lines[1])
+            && self.split_table_row(lines[0]).len() == self.parse_table_alignments(lines[1]).len()
     }
 
     fn is_table_delimiter_row(&self, line: &str) -> bool {
@@ -4579,7 +6004,37 @@ impl Parser {
             .collect()
     }
 
-    fn parse_table_row(&self, row: &str, _alignments: &[Alignment], is_header: bool) -> Vec<Node> {
+    /// Length of a `[[...]]` wiki link at the start of `chars` within a table row, so
+    /// that its alias separator is kept in the cell
+    fn table_wiki_link_len(&self, chars: &[char], in_backticks: bool) -> Option<usize> {
+        if !self.options.wiki_links || in_backticks || !chars.starts_with(&['[', '[']) {
+            return None;
+        }
+        let close = chars[2..].iter().position(|&c| c == '[' || c == ']')? + 2;
+        (chars[close..].starts_with(&[']', ']'])).then_some(close + 2)
+    }
+
+    /// Parse a row into one cell per column, dropping extra cells and padding short
+    /// rows with empty ones
+    fn parse_table_row(&self, row: &str, alignments: &[Alignment], is_header: bool) -> Vec<Node> {
+        let mut cells = self.split_table_row(row);
+        cells.resize(alignments.len(), String::new());
+
+        // Parse cell content as inline markdown
+        cells
+            .into_iter()
+            .map(|cell_text| {
+                let children = self.parse_inline(&cell_text);
+                Node::TableCell {
+                    is_header,
+                    children,
+                }
+            })
+            .collect()
+    }
+
+    /// Split a row into the text of its cells
+    fn split_table_row(&self, row: &str) -> Vec<String> {
         let trimmed = row.trim();
 
         // Split by unescaped pipes
@@ -4598,6 +6053,10 @@ impl Parser {
                 // Escaped pipe
                 current_cell.push('|');
                 i += 2;
+            } else if let Some(len) = self.table_wiki_link_len(&chars[i..], in_backticks) {
+                // The `|` before a wiki link's alias doesn't separate cells
+                current_cell.extend(&chars[i..i + len]);
+                i += len;
             } else if chars[i] == '|' && !in_backticks {
                 // Cell separator
                 cells.push(current_cell.trim().to_string());
@@ -4612,24 +6071,16 @@ impl Parser {
         // Don't forget the last cell
         cells.push(current_cell.trim().to_string());
 
-        // Remove empty leading/trailing cells from pipes at start/end
-        while cells.first().is_some_and(|s| s.is_empty()) {
+        // Remove the empty cells from pipes at start/end
+        if cells.first().is_some_and(|s| s.is_empty()) {
             cells.remove(0);
         }
-        while cells.last().is_some_and(|s| s.is_empty()) {
+        if cells.last().is_some_and(|s| s.is_empty()) {
             cells.pop();
         }
-
-        // Parse cell content as inline markdown
         cells
-            .into_iter()
-            .map(|cell_text| {
-                let children = self.parse_inline(&cell_text);
-                Node::TableCell {
-                    is_header,
-                    children,
-                }
-            })
-            .collect()
     }
 }
+
+#[cfg(test)]
+mod tests;
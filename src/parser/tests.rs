@@ -0,0 +1,785 @@
+//! Tests for the optional syntax enabled through `ParserOptions`
+use super::*;
+use crate::{markdown_to_html, render_with};
+
+fn smart() -> ParserOptions {
+    ParserOptions {
+        smart: true,
+        ..Default::default()
+    }
+}
+
+// Smart punctuation cases are from cmark's smart_punct.txt
+
+#[test]
+fn test_smart_quotes() {
+    let cases = [
+        (
+            "\"Hello,\" said the spider.\n\"'Shelob' is my name.\"\n",
+            "<p>“Hello,” said the spider.\n“‘Shelob’ is my name.”</p>\n",
+        ),
+        (
+            "'A', 'B', and 'C' are letters.\n",
+            "<p>‘A’, ‘B’, and ‘C’ are letters.</p>\n",
+        ),
+        (
+            "'He said, \"I want to go.\"'\n",
+            "<p>‘He said, “I want to go.”’</p>\n",
+        ),
+        (
+            "Were you alive in the 70's?\n",
+            "<p>Were you alive in the 70’s?</p>\n",
+        ),
+        (
+            "Here is some quoted '`code`' and a \"[quoted link](url)\".\n",
+            "<p>Here is some quoted ‘<code>code</code>’ and a “<a href=\"url\">quoted link</a>”.</p>\n",
+        ),
+        (
+            "'tis the season to be 'jolly'\n",
+            "<p>’tis the season to be ‘jolly’</p>\n",
+        ),
+        (
+            "'We'll use Jane's boat and John's truck,' Jenna said.\n",
+            "<p>‘We’ll use Jane’s boat and John’s truck,’ Jenna said.</p>\n",
+        ),
+        (
+            "\"A paragraph with no closing quote.\n\n\"Second paragraph by same speaker, in fiction.\"\n",
+            "<p>“A paragraph with no closing quote.</p>\n<p>“Second paragraph by same speaker, in fiction.”</p>\n",
+        ),
+        ("[a]'s b'\n", "<p>[a]’s b’</p>\n"),
+    ];
+    for (markdown, expected) in cases {
+        assert_eq!(
+            render_with(smart(), markdown),
+            expected,
+            "input: {:?}",
+            markdown
+        );
+    }
+}
+
+#[test]
+fn test_smart_quotes_escaped() {
+    assert_eq!(
+        render_with(
+            smart(),
+            "\\\"This is not smart.\\\"\nThis isn\\'t either.\n5\\'8\\\"\n"
+        ),
+        "<p>&quot;This is not smart.&quot;\nThis isn't either.\n5'8&quot;</p>\n"
+    );
+}
+
+#[test]
+fn test_smart_dashes() {
+    assert_eq!(
+        render_with(
+            smart(),
+            "Some dashes:  em---em\nen--en\nem --- em\nen -- en\n2--3\n"
+        ),
+        "<p>Some dashes:  em—em\nen–en\nem — em\nen – en\n2–3</p>\n"
+    );
+    assert_eq!(
+        render_with(
+            smart(),
+            "one-\ntwo--\nthree---\nfour----\nfive-----\nsix------\nseven-------\n\
+             eight--------\nnine---------\nthirteen-------------.\n"
+        ),
+        "<p>one-\ntwo–\nthree—\nfour––\nfive—–\nsix——\nseven—––\neight––––\nnine———\n\
+         thirteen———––.</p>\n"
+    );
+    assert_eq!(
+        render_with(smart(), "Escaped hyphens: \\-- \\-\\-\\-.\n"),
+        "<p>Escaped hyphens: -- ---.</p>\n"
+    );
+}
+
+#[test]
+fn test_smart_ellipses() {
+    assert_eq!(
+        render_with(smart(), "Ellipses...and...and....\n"),
+        "<p>Ellipses…and…and….</p>\n"
+    );
+    assert_eq!(
+        render_with(smart(), "No ellipses\\.\\.\\.\n"),
+        "<p>No ellipses...</p>\n"
+    );
+}
+
+#[test]
+fn test_smart_punctuation_skips_code_and_html() {
+    assert_eq!(
+        render_with(smart(), "`\"a\" -- b...` <http://x.org/--> <a title='x'>\n"),
+        "<p><code>&quot;a&quot; -- b...</code> <a href=\"http://x.org/--\">http://x.org/--</a> \
+         <a title='x'></p>\n"
+    );
+}
+
+#[test]
+fn test_smart_punctuation_off_by_default() {
+    assert_eq!(
+        markdown_to_html("\"a\" -- b...\n"),
+        "<p>&quot;a&quot; -- b...</p>\n"
+    );
+}
+
+fn math() -> ParserOptions {
+    ParserOptions {
+        math: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_inline_math() {
+    assert_eq!(
+        render_with(math(), "Euler: $e^{i\\pi} + 1 = 0$, not *$a*b$*\n"),
+        "<p>Euler: <span class=\"math inline\">\\(e^{i\\pi} + 1 = 0\\)</span>, not \
+         <em><span class=\"math inline\">\\(a*b\\)</span></em></p>\n"
+    );
+    assert_eq!(
+        render_with(math(), "$b\\$c$ and `$x$`\n"),
+        "<p><span class=\"math inline\">\\(b\\$c\\)</span> and <code>$x$</code></p>\n"
+    );
+}
+
+#[test]
+fn test_display_math() {
+    assert_eq!(
+        render_with(math(), "$$\n\\sum_{i=1}^n x_i < y\n$$\n"),
+        "<p><span class=\"math display\">\\[\n\\sum_{i=1}^n x_i &lt; y\n\\]</span></p>\n"
+    );
+}
+
+#[test]
+fn test_math_fence() {
+    let ast = Parser::with_options(math()).parse("- ```math\n  x^2\n  ```\n");
+    assert_eq!(
+        ast,
+        Node::Document(vec![Node::UnorderedList {
+            tight: true,
+            children: vec![Node::ListItem {
+                tight: true,
+                children: vec![Node::MathBlock("x^2".to_string())],
+            }],
+        }])
+    );
+    assert_eq!(
+        render_with(math(), "```math\nx^2\n```\n"),
+        "<p><span class=\"math display\">\\[x^2\\]</span></p>\n"
+    );
+}
+
+#[test]
+fn test_dollar_rules() {
+    // No space after the opener or before the closer, no digit after the closer, and
+    // an escaped dollar is literal
+    for literal in ["$ x$", "$x $", "$a$1", "\\$x$"] {
+        assert!(
+            !render_with(math(), literal).contains("math"),
+            "input: {:?}",
+            literal
+        );
+    }
+    assert_eq!(
+        render_with(math(), "costs $5 or $10\n"),
+        "<p>costs $5 or $10</p>\n"
+    );
+}
+
+#[test]
+fn test_unclosed_dollars() {
+    // Every opener here fails; later ones must not rescan the rest of the line
+    let input = "$a $ ".repeat(50_000);
+    assert_eq!(
+        render_with(math(), &input),
+        format!("<p>{}</p>\n", input.trim_end())
+    );
+}
+
+#[test]
+fn test_math_off_by_default() {
+    assert_eq!(markdown_to_html("$x$\n"), "<p>$x$</p>\n");
+}
+
+fn front_matter() -> ParserOptions {
+    ParserOptions {
+        front_matter: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_yaml_front_matter() {
+    let ast =
+        Parser::with_options(front_matter()).parse("---\ntitle: Home\ntags: [a, b]\n---\n# Home\n");
+    assert_eq!(
+        ast,
+        Node::Document(vec![
+            Node::FrontMatter {
+                format: FrontMatterFormat::Yaml,
+                raw: "title: Home\ntags: [a, b]\n".to_string(),
+            },
+            Node::Heading {
+                level: 1,
+                children: vec![Node::Text("Home".to_string())],
+                attributes: Attributes::default(),
+            },
+        ])
+    );
+    assert_eq!(
+        render_with(front_matter(), "---\ntitle: Home\n---\n# Home\n"),
+        "<h1>Home</h1>\n"
+    );
+
+    // YAML may also be closed by `...`
+    assert_eq!(render_with(front_matter(), "---\na: 1\n...\n"), "");
+}
+
+#[test]
+fn test_toml_front_matter() {
+    let ast = Parser::with_options(front_matter()).parse("+++\ntitle = \"Home\"\n+++\ntext\n");
+    assert_eq!(
+        ast,
+        Node::Document(vec![
+            Node::FrontMatter {
+                format: FrontMatterFormat::Toml,
+                raw: "title = \"Home\"\n".to_string(),
+            },
+            Node::Paragraph(vec![Node::Text("text".to_string())]),
+        ])
+    );
+}
+
+#[test]
+fn test_front_matter_only_at_start_and_closed() {
+    // Otherwise a thematic break / setext heading
+    assert_eq!(
+        render_with(front_matter(), "\n---\na: 1\n---\n"),
+        "<hr />\n<h2>a: 1</h2>\n"
+    );
+    assert_eq!(
+        render_with(front_matter(), "---\na: 1\n"),
+        "<hr />\n<p>a: 1</p>\n"
+    );
+    assert_eq!(
+        markdown_to_html("---\na: 1\n---\n"),
+        "<hr />\n<h2>a: 1</h2>\n"
+    );
+}
+
+fn definition_lists() -> ParserOptions {
+    ParserOptions {
+        definition_lists: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_tight_definition_list() {
+    // Several definitions, lazy continuation, next term without a blank line
+    assert_eq!(
+        render_with(
+            definition_lists(),
+            "Apple\n: Pomaceous *fruit*.\n: A company\nin Cupertino.\nOrange\n: Citrus.\n"
+        ),
+        "<dl>\n<dt>Apple</dt>\n<dd>Pomaceous <em>fruit</em>.</dd>\n\
+         <dd>A company\nin Cupertino.</dd>\n<dt>Orange</dt>\n<dd>Citrus.</dd>\n</dl>\n"
+    );
+}
+
+#[test]
+fn test_loose_definition_list() {
+    // Multiple terms, blank line before the definition, indented second block
+    assert_eq!(
+        render_with(
+            definition_lists(),
+            "Term 1\nTerm 2\n\n: First.\n\n        code\n\n: Second.\n\nAfter\n"
+        ),
+        "<dl>\n<dt>Term 1</dt>\n<dt>Term 2</dt>\n<dd>\n<p>First.</p>\n\
+         <pre><code>code\n</code></pre>\n</dd>\n<dd>\n<p>Second.</p>\n</dd>\n</dl>\n\
+         <p>After</p>\n"
+    );
+}
+
+#[test]
+fn test_definition_term_must_be_paragraph() {
+    assert_eq!(
+        render_with(definition_lists(), "# Heading\n: text\n"),
+        "<h1>Heading</h1>\n<p>: text</p>\n"
+    );
+    assert_eq!(markdown_to_html("Term\n: def\n"), "<p>Term\n: def</p>\n");
+}
+
+#[test]
+fn test_long_lazy_definition() {
+    // Each lazy line only looks at the next one to tell whether a new term starts
+    let lazy = "word\n".repeat(5_000);
+    assert_eq!(
+        render_with(definition_lists(), &format!("Term\n: {}", lazy)),
+        format!("<dl>\n<dt>Term</dt>\n<dd>{}</dd>\n</dl>\n", lazy.trim_end())
+    );
+}
+
+fn attributes() -> ParserOptions {
+    ParserOptions {
+        attributes: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_heading_attributes() {
+    assert_eq!(
+        render_with(attributes(), "# Title {#intro .lead data-x=\"a b\"}\n"),
+        "<h1 id=\"intro\" class=\"lead\" data-x=\"a b\">Title</h1>\n"
+    );
+    assert_eq!(
+        render_with(attributes(), "Title {#top}\n=====\n"),
+        "<h1 id=\"top\">Title</h1>\n"
+    );
+}
+
+#[test]
+fn test_code_block_attributes() {
+    assert_eq!(
+        render_with(
+            attributes(),
+            "```rust {.numbered #main}\nfn main() {}\n```\n"
+        ),
+        "<pre><code id=\"main\" class=\"language-rust numbered\">fn main() {}\n</code></pre>\n"
+    );
+}
+
+#[test]
+fn test_link_and_image_attributes() {
+    assert_eq!(
+        render_with(
+            attributes(),
+            "[a](/a){.btn target=_blank} ![b](/b.png){width=50}\n"
+        ),
+        "<p><a href=\"/a\" class=\"btn\" target=\"_blank\">a</a> \
+         <img src=\"/b.png\" alt=\"b\" width=\"50\" /></p>\n"
+    );
+}
+
+#[test]
+fn test_span_attributes() {
+    assert_eq!(
+        render_with(attributes(), "[some *text*]{.note} and [x]{}\n"),
+        "<p><span class=\"note\">some <em>text</em></span> and [x]{}</p>\n"
+    );
+}
+
+#[test]
+fn test_invalid_attributes_stay_text() {
+    assert_eq!(
+        render_with(attributes(), "# Title {not attributes}\n"),
+        "<h1>Title {not attributes}</h1>\n"
+    );
+    assert_eq!(
+        markdown_to_html("# Title {#intro}\n"),
+        "<h1>Title {#intro}</h1>\n"
+    );
+}
+
+#[test]
+fn test_event_handler_attributes_rejected() {
+    assert_eq!(
+        render_with(
+            attributes(),
+            "[x]{onclick=\"alert(1)\"} [y](/u){ONLOAD=f}\n"
+        ),
+        "<p>[x]{onclick=&quot;alert(1)&quot;} <a href=\"/u\">y</a>{ONLOAD=f}</p>\n"
+    );
+}
+
+#[test]
+fn test_span_brackets_skip_code_spans() {
+    assert_eq!(
+        render_with(attributes(), "[a `]` b]{.x}\n"),
+        "<p><span class=\"x\">a <code>]</code> b</span></p>\n"
+    );
+}
+
+#[test]
+fn test_several_heading_attribute_blocks() {
+    assert_eq!(
+        render_with(attributes(), "# t {#x .a}{#y .b}\n"),
+        "<h1 id=\"y\" class=\"a b\">t</h1>\n"
+    );
+}
+
+fn alerts() -> ParserOptions {
+    ParserOptions {
+        alerts: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_alert() {
+    assert_eq!(
+        render_with(alerts(), "> [!NOTE]\n> Useful *information*.\n"),
+        "<div class=\"markdown-alert markdown-alert-note\">\n\
+         <p class=\"markdown-alert-title\">Note</p>\n\
+         <p>Useful <em>information</em>.</p>\n</div>\n"
+    );
+}
+
+#[test]
+fn test_alert_kind_is_case_insensitive() {
+    assert_eq!(
+        render_with(alerts(), "> [!caution]\n> - one\n> - two\n"),
+        "<div class=\"markdown-alert markdown-alert-caution\">\n\
+         <p class=\"markdown-alert-title\">Caution</p>\n\
+         <ul>\n<li>one</li>\n<li>two</li>\n</ul>\n</div>\n"
+    );
+}
+
+#[test]
+fn test_invalid_alerts_stay_blockquotes() {
+    // Unknown kinds, markers with trailing text and empty alerts
+    assert_eq!(
+        render_with(alerts(), "> [!FOO]\n> text\n"),
+        "<blockquote>\n<p>[!FOO]\ntext</p>\n</blockquote>\n"
+    );
+    assert_eq!(
+        render_with(alerts(), "> [!TIP] text\n"),
+        "<blockquote>\n<p>[!TIP] text</p>\n</blockquote>\n"
+    );
+    assert_eq!(
+        render_with(alerts(), "> [!TIP]\n"),
+        "<blockquote>\n<p>[!TIP]</p>\n</blockquote>\n"
+    );
+    assert_eq!(
+        markdown_to_html("> [!NOTE]\n> text\n"),
+        "<blockquote>\n<p>[!NOTE]\ntext</p>\n</blockquote>\n"
+    );
+}
+
+fn containers() -> ParserOptions {
+    ParserOptions {
+        containers: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_container() {
+    assert_eq!(
+        render_with(containers(), "::: warning\n*here be dragons*\n:::\n"),
+        "<div class=\"warning\">\n<p><em>here be dragons</em></p>\n</div>\n"
+    );
+    assert_eq!(
+        markdown_to_html("::: note\ntext\n:::\n"),
+        "<p>::: note\ntext\n:::</p>\n"
+    );
+}
+
+#[test]
+fn test_nested_containers() {
+    // Nested containers use fewer colons; unclosed ones run to the end
+    assert_eq!(
+        render_with(
+            containers(),
+            ":::: tabs\n::: tab One\nfirst\n:::\n::: tab Two\nsecond\n"
+        ),
+        "<div class=\"tabs\">\n<div class=\"tab\">\n<p>first</p>\n</div>\n\
+         <div class=\"tab\">\n<p>second</p>\n</div>\n</div>\n"
+    );
+}
+
+#[test]
+fn test_container_interrupts_paragraph() {
+    // A bare `:::` doesn't open one
+    assert_eq!(
+        render_with(containers(), "text\n::: note\nbody\n:::\n:::\n"),
+        "<p>text</p>\n<div class=\"note\">\n<p>body</p>\n</div>\n<p>:::</p>\n"
+    );
+}
+
+#[test]
+fn test_container_fenced_code() {
+    // Colons inside fenced code don't close the container
+    assert_eq!(
+        render_with(containers(), "::: example\n```\n:::\n```\nafter\n:::\n"),
+        "<div class=\"example\">\n<pre><code>:::\n</code></pre>\n<p>after</p>\n</div>\n"
+    );
+}
+
+fn inline_delimiters() -> ParserOptions {
+    ParserOptions {
+        superscript: true,
+        subscript: true,
+        highlight: true,
+        insert: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_inline_delimiter_extensions() {
+    assert_eq!(
+        render_with(
+            inline_delimiters(),
+            "x^2^ H~2~O ==marked *text*== ++new++\n"
+        ),
+        "<p>x<sup>2</sup> H<sub>2</sub>O <mark>marked <em>text</em></mark> <ins>new</ins></p>\n"
+    );
+    assert_eq!(markdown_to_html("x^2^ ==a==\n"), "<p>x^2^ ==a==</p>\n");
+}
+
+#[test]
+fn test_inline_delimiters_nest_with_emphasis() {
+    assert_eq!(
+        render_with(inline_delimiters(), "*a ^b* c^\n"),
+        "<p><em>a ^b</em> c^</p>\n"
+    );
+}
+
+#[test]
+fn test_unmatched_inline_delimiters_stay_text() {
+    // Other run lengths and unflanked delimiters
+    assert_eq!(
+        render_with(inline_delimiters(), "~~no~~ ===no=== C++ and a ^ b ^\n"),
+        "<p>~~no~~ ===no=== C++ and a ^ b ^</p>\n"
+    );
+}
+
+fn emoji() -> ParserOptions {
+    ParserOptions {
+        emoji: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_emoji_shortcodes() {
+    assert_eq!(
+        render_with(emoji(), ":smile: *:+1:* :nope:\n"),
+        "<p>😄 <em>👍</em> :nope:</p>\n"
+    );
+    assert_eq!(markdown_to_html(":smile:\n"), "<p>:smile:</p>\n");
+}
+
+#[test]
+fn test_emoji_not_in_code_times_or_urls() {
+    assert_eq!(
+        render_with(emoji(), "`:smile:` 10:30:00 <http://x/:smile:>\n"),
+        "<p><code>:smile:</code> 10:30:00 <a href=\"http://x/:smile:\">http://x/:smile:</a></p>\n"
+    );
+    assert_eq!(
+        render_with(emoji(), "snake_case_:smile: a_:smile:\n"),
+        "<p>snake_case_:smile: a_:smile:</p>\n"
+    );
+}
+
+fn wiki_links() -> ParserOptions {
+    ParserOptions {
+        wiki_links: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_wiki_links() {
+    assert_eq!(
+        render_with(
+            wiki_links(),
+            "[[Home]] [[Setup Guide#Install|the *install* steps]] [[#Notes]]\n"
+        ),
+        "<p><a href=\"Home\" class=\"wikilink\">Home</a> \
+         <a href=\"Setup%20Guide#Install\" class=\"wikilink\">the <em>install</em> steps</a> \
+         <a href=\"#Notes\" class=\"wikilink\">#Notes</a></p>\n"
+    );
+    assert_eq!(markdown_to_html("[[Home]]\n"), "<p>[[Home]]</p>\n");
+}
+
+#[test]
+fn test_invalid_wiki_links_stay_text() {
+    // Empty targets, brackets and line breaks
+    assert_eq!(
+        render_with(wiki_links(), "[[]] [[a[b]]] [[a\nb]]\n"),
+        "<p>[[]] [[a[b]]] [[a\nb]]</p>\n"
+    );
+}
+
+#[test]
+fn test_wiki_links_after_code_spans() {
+    assert_eq!(
+        render_with(wiki_links(), "[[a`]]`\n"),
+        "<p>[[a<code>]]</code></p>\n"
+    );
+}
+
+#[test]
+fn test_wiki_link_alias_in_table() {
+    assert_eq!(
+        render_with(wiki_links(), "| Page |\n| --- |\n| [[Home|start]] |\n"),
+        "<table>\n<thead>\n<tr>\n<th>Page</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n\
+         <td><a href=\"Home\" class=\"wikilink\">start</a></td>\n</tr>\n</tbody>\n</table>\n"
+    );
+}
+
+fn mentions() -> ParserOptions {
+    ParserOptions {
+        mentions: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_mentions_and_references() {
+    let ast = Parser::with_options(mentions()).parse("@octo-cat #12 rust-lang/rust#1 #release\n");
+    assert_eq!(
+        ast,
+        Node::Document(vec![Node::Paragraph(vec![
+            Node::Mention("octo-cat".to_string()),
+            Node::Text(" ".to_string()),
+            Node::Reference {
+                kind: ReferenceKind::Issue,
+                repository: None,
+                id: "12".to_string(),
+            },
+            Node::Text(" ".to_string()),
+            Node::Reference {
+                kind: ReferenceKind::Issue,
+                repository: Some("rust-lang/rust".to_string()),
+                id: "1".to_string(),
+            },
+            Node::Text(" ".to_string()),
+            Node::Reference {
+                kind: ReferenceKind::Tag,
+                repository: None,
+                id: "release".to_string(),
+            },
+        ])])
+    );
+}
+
+#[test]
+fn test_mentions_render_as_text_without_resolver() {
+    assert_eq!(
+        render_with(mentions(), "Thanks @octo-cat, see #12 and *#release*.\n"),
+        "<p>Thanks @octo-cat, see #12 and <em>#release</em>.</p>\n"
+    );
+}
+
+#[test]
+fn test_mentions_not_in_code_links_or_words() {
+    assert_eq!(
+        render_with(
+            mentions(),
+            "`@code` [@link](/x) me@example.com a#1 #1a @-no\n"
+        ),
+        "<p><code>@code</code> <a href=\"/x\">@link</a> me@example.com a#1 #1a @-no</p>\n"
+    );
+}
+
+#[test]
+fn test_escaped_mentions_stay_text() {
+    let ast = Parser::with_options(mentions()).parse("\\#123 \\@bob &#35;12 &#64;bob\n");
+    let Node::Document(blocks) = ast else {
+        panic!("expected a document");
+    };
+    let [Node::Paragraph(children)] = blocks.as_slice() else {
+        panic!("expected one paragraph, got {:?}", blocks);
+    };
+    assert!(
+        children.iter().all(|node| matches!(node, Node::Text(_))),
+        "{:?}",
+        children
+    );
+}
+
+fn abbreviations() -> ParserOptions {
+    ParserOptions {
+        abbreviations: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_abbreviations() {
+    // Definitions may come after their use and are removed from the output
+    assert_eq!(
+        render_with(
+            abbreviations(),
+            "The HTML and *W3C HTML* specs, not HTMLX or `HTML`.\n\
+             *[HTML]: Hyper Text Markup Language\n\
+             *[W3C HTML]: The \"W3C\" HTML spec\n\
+             *[W3C]:  World Wide Web Consortium\n"
+        ),
+        "<p>The <abbr title=\"Hyper Text Markup Language\">HTML</abbr> and \
+         <em><abbr title=\"The &quot;W3C&quot; HTML spec\">W3C HTML</abbr></em> specs, \
+         not HTMLX or <code>HTML</code>.</p>\n"
+    );
+}
+
+#[test]
+fn test_abbreviation_definitions_apply_document_wide() {
+    // Definitions inside blockquotes apply to the whole document; code is left alone
+    assert_eq!(
+        render_with(
+            abbreviations(),
+            "> *[API]: Application Programming Interface\n\n    *[API]: code\n\nAPI\n"
+        ),
+        "<blockquote>\n</blockquote>\n<pre><code>*[API]: code\n</code></pre>\n\
+         <p><abbr title=\"Application Programming Interface\">API</abbr></p>\n"
+    );
+}
+
+#[test]
+fn test_abbreviations_disabled() {
+    assert_eq!(
+        markdown_to_html("HTML\n\n*[HTML]: Hyper Text\n"),
+        "<p>HTML</p>\n<p>*[HTML]: Hyper Text</p>\n"
+    );
+}
+
+fn heading_ids(auto_heading_ids: bool) -> ParserOptions {
+    ParserOptions {
+        heading_ids: true,
+        auto_heading_ids,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_heading_attribute_blocks() {
+    assert_eq!(
+        render_with(
+            heading_ids(false),
+            "# Install {#setup .wide} #\nNotes {#notes}\n---\n"
+        ),
+        "<h1 id=\"setup\" class=\"wide\">Install</h1>\n<h2 id=\"notes\">Notes</h2>\n"
+    );
+}
+
+#[test]
+fn test_auto_heading_ids() {
+    // Explicit ids replace generated slugs, which stay unique
+    assert_eq!(
+        render_with(
+            heading_ids(true),
+            "# Hello, *World*!\n## Hello World {#hello-world}\n- > ### Hello World\n\n# Hello World\n"
+        ),
+        "<h1 id=\"hello-world-1\">Hello, <em>World</em>!</h1>\n\
+         <h2 id=\"hello-world\">Hello World</h2>\n<ul>\n<li>\n<blockquote>\n\
+         <h3 id=\"hello-world-2\">Hello World</h3>\n</blockquote>\n</li>\n</ul>\n\
+         <h1 id=\"hello-world-3\">Hello World</h1>\n"
+    );
+}
+
+#[test]
+fn test_heading_ids_only_on_headings() {
+    // Without the option the block is heading text; other blocks don't take attributes
+    assert_eq!(
+        markdown_to_html("# Title {#id}\n"),
+        "<h1>Title {#id}</h1>\n"
+    );
+    assert_eq!(
+        render_with(heading_ids(false), "```rust {#main}\n```\n"),
+        "<pre><code class=\"language-rust\"></code></pre>\n"
+    );
+}
@@ -0,0 +1,380 @@
+use crate::ast::Alignment;
+/// Plain-text renderer for CommonMark AST
+use crate::ast::Node;
+use crate::renderer::reference_text;
+
+/// Options controlling the plain-text renderer
+#[derive(Debug, Clone, Default)]
+pub struct PlainTextOptions {
+    /// Follow link text with the destination in parentheses, unless the text already
+    /// is the destination (as for autolinks)
+    pub link_urls: bool,
+}
+
+/// Renders a tree as readable text without markup: blocks are separated by blank
+/// lines, list items get `-` or `1.` markers, quotes get `> ` and code is indented
+pub struct PlainTextRenderer {
+    options: PlainTextOptions,
+}
+
+impl PlainTextRenderer {
+    pub fn new() -> Self {
+        Self::with_options(PlainTextOptions::default())
+    }
+
+    pub fn with_options(options: PlainTextOptions) -> Self {
+        PlainTextRenderer { options }
+    }
+
+    pub fn render(&self, node: &Node) -> String {
+        let text = match node {
+            Node::Document(children) => self.render_blocks(children, false),
+            _ => self.render_blocks(std::slice::from_ref(node), false),
+        };
+        if text.is_empty() { text } else { text + "\n" }
+    }
+
+    /// Render inline nodes to text, keeping line breaks
+    pub fn render_inline(&self, nodes: &[Node]) -> String {
+        nodes
+            .iter()
+            .map(|node| self.render_inline_node(node))
+            .collect()
+    }
+
+    fn render_inline_node(&self, node: &Node) -> String {
+        match node {
+            Node::Text(text) => text.clone(),
+            Node::Code(code) | Node::InlineMath(code) | Node::DisplayMath(code) => code.clone(),
+            Node::Emphasis(children)
+            | Node::Strong(children)
+            | Node::Superscript(children)
+            | Node::Subscript(children)
+            | Node::Mark(children)
+            | Node::Insert(children)
+            | Node::WikiLink { children, .. }
+            | Node::Span { children, .. } => self.render_inline(children),
+            Node::Link {
+                destination,
+                children,
+                ..
+            } => {
+                let text = self.render_inline(children);
+                let is_autolink = text == *destination
+                    || destination.strip_prefix("mailto:") == Some(text.as_str());
+                if self.options.link_urls && !is_autolink {
+                    format!("{} ({})", text, destination)
+                } else {
+                    text
+                }
+            }
+            Node::Image { alt_text, .. } => self.render_inline(alt_text),
+            Node::Emoji { unicode, .. } => unicode.clone(),
+            Node::Mention(_) | Node::Reference { .. } => reference_text(node),
+            Node::Abbreviation { text, .. } => text.clone(),
+            Node::HardBreak => "\n".to_string(),
+            // Raw HTML has no readable text, and blocks are rendered by render_block
+            Node::HtmlInline(_)
+            | Node::Document(_)
+            | Node::FrontMatter { .. }
+            | Node::Paragraph(_)
+            | Node::Heading { .. }
+            | Node::CodeBlock { .. }
+            | Node::MathBlock(_)
+            | Node::ThematicBreak
+            | Node::BlockQuote(_)
+            | Node::Alert { .. }
+            | Node::Container { .. }
+            | Node::LinkReferenceDefinition { .. }
+            | Node::UnorderedList { .. }
+            | Node::OrderedList { .. }
+            | Node::ListItem { .. }
+            | Node::DefinitionList(_)
+            | Node::DefinitionTerm(_)
+            | Node::DefinitionDetails { .. }
+            | Node::HtmlBlock(_)
+            | Node::Table { .. }
+            | Node::TableRow(_)
+            | Node::TableCell { .. } => String::new(),
+        }
+    }
+
+    /// Render a sequence of blocks, separated by a newline if `tight`, else a blank line.
+    /// Runs of inline nodes (as in tight list items) are rendered as one block.
+    fn render_blocks(&self, nodes: &[Node], tight: bool) -> String {
+        let mut blocks = Vec::new();
+        let mut inline = Vec::new();
+
+        for node in nodes {
+            if is_block(node) {
+                if !inline.is_empty() {
+                    blocks.push(self.render_inline(&inline));
+                    inline.clear();
+                }
+                blocks.push(self.render_block(node));
+            } else {
+                inline.push(node.clone());
+            }
+        }
+        if !inline.is_empty() {
+            blocks.push(self.render_inline(&inline));
+        }
+
+        blocks.retain(|block| !block.is_empty());
+        blocks.join(if tight { "\n" } else { "\n\n" })
+    }
+
+    fn render_block(&self, node: &Node) -> String {
+        match node {
+            Node::Document(children) | Node::Container { children, .. } => {
+                self.render_blocks(children, false)
+            }
+            Node::Paragraph(children) | Node::Heading { children, .. } => {
+                self.render_inline(children)
+            }
+            Node::CodeBlock { literal, .. } => {
+                prefix_lines(literal.trim_end_matches('\n'), "    ", "    ")
+            }
+            Node::MathBlock(math) => prefix_lines(math, "    ", "    "),
+            Node::ThematicBreak => "----".to_string(),
+            Node::BlockQuote(children) => {
+                prefix_lines(&self.render_blocks(children, false), "> ", "> ")
+            }
+            Node::Alert { kind, children } => {
+                let content = self.render_blocks(children, false);
+                prefix_lines(&format!("{}\n{}", kind.title(), content), "> ", "> ")
+            }
+            Node::UnorderedList { tight, children } => {
+                let items: Vec<String> = children
+                    .iter()
+                    .map(|item| self.render_list_item(item, "- "))
+                    .collect();
+                items.join(if *tight { "\n" } else { "\n\n" })
+            }
+            Node::OrderedList {
+                start,
+                tight,
+                children,
+            } => {
+                let items: Vec<String> = children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let marker = format!("{}. ", *start as usize + i);
+                        self.render_list_item(item, &marker)
+                    })
+                    .collect();
+                items.join(if *tight { "\n" } else { "\n\n" })
+            }
+            Node::ListItem { .. } => self.render_list_item(node, "- "),
+            Node::DefinitionList(children) => {
+                let parts: Vec<String> = children
+                    .iter()
+                    .map(|child| match child {
+                        Node::DefinitionTerm(term) => self.render_inline(term),
+                        Node::DefinitionDetails { tight, children } => {
+                            prefix_lines(&self.render_blocks(children, *tight), "    ", "    ")
+                        }
+                        _ => self.render_block(child),
+                    })
+                    .collect();
+                parts.join("\n")
+            }
+            Node::Table {
+                alignments,
+                children,
+            } => self.render_table(alignments, children),
+            // Raw HTML and metadata have no readable text
+            Node::HtmlBlock(_)
+            | Node::FrontMatter { .. }
+            | Node::LinkReferenceDefinition { .. } => String::new(),
+            _ => self.render_inline_node(node),
+        }
+    }
+
+    /// Render a list item with `marker` before its first line and the following lines
+    /// indented to line up with it
+    fn render_list_item(&self, item: &Node, marker: &str) -> String {
+        let content = match item {
+            Node::ListItem { tight, children } => self.render_blocks(children, *tight),
+            _ => self.render_block(item),
+        };
+        prefix_lines(&content, marker, &" ".repeat(marker.len()))
+    }
+
+    /// Render a table as columns padded to their widest cell, with a rule under the header
+    fn render_table(&self, alignments: &[Alignment], rows: &[Node]) -> String {
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| match row {
+                Node::TableRow(cells) => cells
+                    .iter()
+                    .map(|cell| match cell {
+                        Node::TableCell { children, .. } => {
+                            self.render_inline(children).replace('\n', " ")
+                        }
+                        _ => String::new(),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(3)
+            })
+            .collect();
+
+        let format_row = |row: &[String]| {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, &width)| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or("");
+                    let padding = width - cell.chars().count();
+                    match alignments.get(column).unwrap_or(&Alignment::None) {
+                        Alignment::Right => format!("{}{}", " ".repeat(padding), cell),
+                        Alignment::Center => format!(
+                            "{}{}{}",
+                            " ".repeat(padding / 2),
+                            cell,
+                            " ".repeat(padding - padding / 2)
+                        ),
+                        Alignment::Left | Alignment::None => {
+                            format!("{}{}", cell, " ".repeat(padding))
+                        }
+                    }
+                })
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        };
+
+        let mut lines = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            lines.push(format_row(row));
+            if i == 0 {
+                let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+                lines.push(rule.join("  "));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+impl Default for PlainTextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Convert inline nodes to plain text (for image alt text and heading slugs)
+/// This strips all formatting and just keeps the text content
+pub(crate) fn alt_text_to_string(nodes: &[Node]) -> String {
+    PlainTextRenderer::new().render_inline(nodes)
+}
+
+/// Whether a node is rendered as a block of its own
+pub(crate) fn is_block(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Document(_)
+            | Node::FrontMatter { .. }
+            | Node::Paragraph(_)
+            | Node::Heading { .. }
+            | Node::CodeBlock { .. }
+            | Node::MathBlock(_)
+            | Node::ThematicBreak
+            | Node::BlockQuote(_)
+            | Node::Alert { .. }
+            | Node::Container { .. }
+            | Node::UnorderedList { .. }
+            | Node::OrderedList { .. }
+            | Node::ListItem { .. }
+            | Node::DefinitionList(_)
+            | Node::HtmlBlock(_)
+            | Node::LinkReferenceDefinition { .. }
+            | Node::Table { .. }
+    )
+}
+
+/// Prefix the first line of `text` with `first` and the others with `rest`, leaving
+/// blank lines without trailing whitespace
+pub(crate) fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    let lines: Vec<String> = text
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_plain_text_blocks() {
+        let ast = Parser::new().parse(
+            "# Release *notes*\n\n\
+             Read the [guide](https://example.com/guide) or <https://example.com>.\n\n\
+             - one\n- two\n  continued\n\n\
+             3. first\n\n   second paragraph\n4. next\n\n\
+             > quoted\n>\n> again\n\n\
+             ```\nfn main() {}\n```\n\n\
+             <div>raw</div>\n\n\
+             ***\n",
+        );
+        assert_eq!(
+            PlainTextRenderer::new().render(&ast),
+            "Release notes\n\n\
+             Read the guide or https://example.com.\n\n\
+             - one\n- two\n  continued\n\n\
+             3. first\n\n   second paragraph\n\n4. next\n\n\
+             > quoted\n>\n> again\n\n\
+             \x20   fn main() {}\n\n\
+             ----\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_text_tables() {
+        let ast =
+            Parser::new().parse("| Name | Qty |\n| :-- | --: |\n| apple | 10 |\n| kiwi | 2 |\n");
+        assert_eq!(
+            PlainTextRenderer::new().render(&ast),
+            "Name   Qty\n-----  ---\napple   10\nkiwi     2\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_text_link_urls() {
+        let renderer = PlainTextRenderer::with_options(PlainTextOptions { link_urls: true });
+        assert_eq!(
+            renderer.render(&Parser::new().parse("[guide](/guide) <https://example.com>\n")),
+            "guide (/guide) https://example.com\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_text_empty_document() {
+        assert_eq!(
+            PlainTextRenderer::new().render(&Parser::new().parse("")),
+            ""
+        );
+    }
+}
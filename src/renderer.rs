@@ -1,287 +1,730 @@
 use crate::ast::Alignment;
+use crate::ast::Attributes;
 /// HTML renderer for CommonMark AST
 use crate::ast::Node;
+use crate::ast::ReferenceKind;
+use crate::emoji;
+use crate::parser::url_encode;
+use crate::plain_text::alt_text_to_string;
+use std::collections::HashMap;
 
-pub struct HtmlRenderer;
+/// What a destination passed to the URL rewriter belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlKind {
+    Link,
+    Image,
+}
+
+/// Maps a link or image destination to the URL written into the HTML
+pub type UrlRewriter = Box<dyn Fn(UrlKind, &str) -> String + Send + Sync>;
+
+/// Maps a wiki link page name to its URL, or `None` if the page doesn't exist
+pub type WikiLinkResolver = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Maps a `Node::Mention` or `Node::Reference` to its URL, or `None` to render it as
+/// plain text
+pub type ReferenceResolver = Box<dyn Fn(&Node) -> Option<String> + Send + Sync>;
+
+/// Renders a `Node::Container` from its args and its already rendered children
+pub type ContainerRenderer = Box<dyn Fn(&str, &str) -> String + Send + Sync>;
+
+/// Options controlling the attributes the HTML renderer adds
+#[derive(Debug, Clone, Default)]
+pub struct RendererOptions {
+    /// Host of the site being rendered (e.g. `example.com`); links with any other host
+    /// are external. When unset, every link with a host is external.
+    pub site_host: Option<String>,
+    /// Add `rel="nofollow"` to external links
+    pub nofollow_external_links: bool,
+    /// Add `target="_blank"` and `rel="noopener"` to external links
+    pub external_links_new_tab: bool,
+    /// Add `loading="lazy"` and `decoding="async"` to images
+    pub lazy_images: bool,
+    /// Render emoji as `<img class="emoji">` whose `src` is this prefix followed by the
+    /// emoji's code points and `.png` (e.g. `https://example.com/emoji/1f604.png`)
+    /// instead of as Unicode text
+    pub emoji_image_url: Option<String>,
+}
+
+pub struct HtmlRenderer {
+    options: RendererOptions,
+    /// Applied to every link and image destination before it is escaped
+    url_rewriter: Option<UrlRewriter>,
+    /// Custom HTML for containers, by container name
+    container_renderers: HashMap<String, ContainerRenderer>,
+    /// Resolves wiki link page names; without one every page exists at its own name
+    wiki_link_resolver: Option<WikiLinkResolver>,
+    /// Links mentions and references; without one they render as plain text
+    reference_resolver: Option<ReferenceResolver>,
+}
 
 impl HtmlRenderer {
     pub fn new() -> Self {
-        HtmlRenderer
+        Self::with_options(RendererOptions::default())
     }
 
-    pub fn render(&self, node: &Node) -> String {
-        render_node(node)
+    pub fn with_options(options: RendererOptions) -> Self {
+        HtmlRenderer {
+            options,
+            url_rewriter: None,
+            container_renderers: HashMap::new(),
+            wiki_link_resolver: None,
+            reference_resolver: None,
+        }
     }
-}
 
-impl Default for HtmlRenderer {
-    fn default() -> Self {
-        Self::new()
+    /// Set a callback invoked for every `Node::Link` and `Node::Image` destination
+    /// (already percent-encoded by the parser) and every resolved `Node::WikiLink`
+    /// URL; its result is HTML-escaped and used as the `href`/`src`
+    pub fn set_url_rewriter<F>(&mut self, rewriter: F)
+    where
+        F: Fn(UrlKind, &str) -> String + Send + Sync + 'static,
+    {
+        self.url_rewriter = Some(Box::new(rewriter));
     }
-}
 
-fn render_node(node: &Node) -> String {
-    match node {
-        Node::Document(children) => children.iter().map(render_node).collect(),
-        Node::Paragraph(children) => {
-            let content: String = children.iter().map(render_node).collect();
-            format!("<p>{}</p>\n", content)
+    /// Set the HTML for containers named `name`, replacing the default
+    /// `<div class="name">` wrapper
+    pub fn set_container_renderer<F>(&mut self, name: &str, renderer: F)
+    where
+        F: Fn(&str, &str) -> String + Send + Sync + 'static,
+    {
+        self.container_renderers
+            .insert(name.to_string(), Box::new(renderer));
+    }
+
+    /// Set a callback resolving `Node::WikiLink` page names to URLs. Pages it returns
+    /// `None` for are missing: they link to the page name and get the
+    /// `wikilink-missing` class.
+    pub fn set_wiki_link_resolver<F>(&mut self, resolver: F)
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.wiki_link_resolver = Some(Box::new(resolver));
+    }
+
+    /// Set a callback producing the URLs of `@user` mentions and `#123`,
+    /// `owner/repo#123` and `#tag` references
+    pub fn set_reference_resolver<F>(&mut self, resolver: F)
+    where
+        F: Fn(&Node) -> Option<String> + Send + Sync + 'static,
+    {
+        self.reference_resolver = Some(Box::new(resolver));
+    }
+
+    fn rewrite_url(&self, kind: UrlKind, destination: &str) -> String {
+        match &self.url_rewriter {
+            Some(rewriter) => rewriter(kind, destination),
+            None => destination.to_string(),
+        }
+    }
+
+    /// Extra attributes for a link to `destination`, with a leading space
+    fn link_attributes(&self, destination: &str) -> String {
+        let options = &self.options;
+        if !options.nofollow_external_links && !options.external_links_new_tab {
+            return String::new();
+        }
+
+        let is_external = match url_host(destination) {
+            Some(host) => options
+                .site_host
+                .as_ref()
+                .is_none_or(|site| !site.eq_ignore_ascii_case(&host)),
+            None => false,
+        };
+        if !is_external {
+            return String::new();
         }
-        Node::Heading { level, children } => {
-            let content: String = children.iter().map(render_node).collect();
-            format!("<h{}>{}</h{}>\n", level, content, level)
+
+        let mut rel = Vec::new();
+        if options.nofollow_external_links {
+            rel.push("nofollow");
         }
-        Node::CodeBlock { info, literal } => {
-            if info.is_empty() {
-                format!("<pre><code>{}</code></pre>\n", escape_html(literal))
-            } else {
+        if options.external_links_new_tab {
+            rel.push("noopener");
+        }
+        let mut attributes = format!(" rel=\"{}\"", rel.join(" "));
+        if options.external_links_new_tab {
+            attributes.push_str(" target=\"_blank\"");
+        }
+        attributes
+    }
+
+    /// Extra attributes for an image, with a leading space
+    fn image_attributes(&self) -> &'static str {
+        if self.options.lazy_images {
+            " loading=\"lazy\" decoding=\"async\""
+        } else {
+            ""
+        }
+    }
+
+    pub fn render(&self, node: &Node) -> String {
+        self.render_node(node)
+    }
+
+    fn render_node(&self, node: &Node) -> String {
+        match node {
+            Node::FrontMatter { .. } => String::new(), // Metadata, not content
+            Node::Document(children) => children
+                .iter()
+                .map(|child| self.render_node(child))
+                .collect(),
+            Node::Paragraph(children) => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<p>{}</p>\n", content)
+            }
+            Node::Heading {
+                level,
+                children,
+                attributes,
+            } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
                 format!(
-                    "<pre><code class=\"language-{}\">{}</code></pre>\n",
-                    escape_html(info),
+                    "<h{}{}>{}</h{}>\n",
+                    level,
+                    render_attributes(attributes, None),
+                    content,
+                    level
+                )
+            }
+            Node::CodeBlock {
+                info,
+                literal,
+                attributes,
+            } => {
+                let language = (!info.is_empty()).then(|| format!("language-{}", info));
+                format!(
+                    "<pre><code{}>{}</code></pre>\n",
+                    render_attributes(attributes, language.as_deref()),
                     escape_html(literal)
                 )
             }
-        }
-        Node::ThematicBreak => "<hr />\n".to_string(),
-        Node::BlockQuote(children) => {
-            let content: String = children.iter().map(render_node).collect();
-            format!("<blockquote>\n{}</blockquote>\n", content)
-        }
-        Node::UnorderedList { tight: _, children } => {
-            let content: String = children.iter().map(render_node).collect();
-            format!("<ul>\n{}</ul>\n", content)
-        }
-        Node::OrderedList {
-            start,
-            tight: _,
-            children,
-        } => {
-            let content: String = children.iter().map(render_node).collect();
-            if *start == 1 {
-                format!("<ol>\n{}</ol>\n", content)
-            } else {
-                format!("<ol start=\"{}\">\n{}</ol>\n", start, content)
+            // Rendered like a paragraph holding only display math, as pandoc does
+            Node::MathBlock(math) => format!(
+                "<p><span class=\"math display\">\\[{}\\]</span></p>\n",
+                escape_html(math)
+            ),
+            Node::ThematicBreak => "<hr />\n".to_string(),
+            Node::BlockQuote(children) => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<blockquote>\n{}</blockquote>\n", content)
             }
-        }
-        Node::ListItem { tight, children } => {
-            // Determine if this item should render its paragraphs with <p> tags
-            // If tight is true, single paragraphs are unwrapped
-
-            // Check if we have a mix of inline and block content
-            let has_blocks = children.iter().any(|child| {
-                matches!(
-                    child,
-                    Node::Paragraph(_)
-                        | Node::BlockQuote(_)
-                        | Node::CodeBlock { .. }
-                        | Node::UnorderedList { .. }
-                        | Node::OrderedList { .. }
-                        | Node::ThematicBreak
-                        | Node::HtmlBlock(_)
+            // Same markup as GitHub, minus the icon
+            Node::Alert { kind, children } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!(
+                    "<div class=\"markdown-alert markdown-alert-{}\">\n\
+                     <p class=\"markdown-alert-title\">{}</p>\n{}</div>\n",
+                    kind.name(),
+                    kind.title(),
+                    content
                 )
-            });
-
-            if *tight && children.len() == 1 {
-                // Tight item with single child - unwrap paragraph if it's the only content
-                match &children[0] {
-                    Node::Paragraph(para_children) => {
-                        let content: String = para_children.iter().map(render_node).collect();
-                        return format!("<li>{}</li>\n", content.trim_end());
-                    }
-                    _ => {
-                        // Single non-paragraph block
-                        let content = render_node(&children[0]);
-                        if content.ends_with('\n') {
-                            return format!("<li>\n{}</li>\n", content);
-                        } else {
-                            return format!("<li>{}</li>\n", content);
-                        }
-                    }
+            }
+            Node::Container {
+                name,
+                args,
+                children,
+            } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                match self.container_renderers.get(name) {
+                    Some(renderer) => renderer(args, &content),
+                    None => format!("<div class=\"{}\">\n{}</div>\n", escape_html(name), content),
+                }
+            }
+            Node::UnorderedList { tight: _, children } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<ul>\n{}</ul>\n", content)
+            }
+            Node::OrderedList {
+                start,
+                tight: _,
+                children,
+            } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                if *start == 1 {
+                    format!("<ol>\n{}</ol>\n", content)
+                } else {
+                    format!("<ol start=\"{}\">\n{}</ol>\n", start, content)
                 }
             }
+            Node::ListItem { tight, children } => self.render_list_item("li", *tight, children),
+            Node::DefinitionList(children) => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<dl>\n{}</dl>\n", content)
+            }
+            Node::DefinitionTerm(children) => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<dt>{}</dt>\n", content)
+            }
+            Node::DefinitionDetails { tight, children } => {
+                self.render_list_item("dd", *tight, children)
+            }
+            Node::Text(text) => escape_html(text),
+            Node::Code(code) => format!("<code>{}</code>", escape_html(code)),
+            Node::Emphasis(children) => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<em>{}</em>", content)
+            }
+            Node::Strong(children) => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<strong>{}</strong>", content)
+            }
+            Node::Superscript(children)
+            | Node::Subscript(children)
+            | Node::Mark(children)
+            | Node::Insert(children) => {
+                let tag = match node {
+                    Node::Superscript(_) => "sup",
+                    Node::Subscript(_) => "sub",
+                    Node::Mark(_) => "mark",
+                    _ => "ins",
+                };
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!("<{}>{}</{}>", tag, content, tag)
+            }
+            Node::WikiLink {
+                page,
+                anchor,
+                children,
+            } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
 
-            if has_blocks {
-                // Render inline elements first (if any) on the same line as <li>
-                let mut inline_content = String::new();
-                let mut block_content = String::new();
-
-                for child in children {
-                    match child {
-                        Node::Text(_)
-                        | Node::Code(_)
-                        | Node::Emphasis(_)
-                        | Node::Strong(_)
-                        | Node::Link { .. }
-                        | Node::Image { .. }
-                        | Node::HtmlInline(_)
-                        | Node::HardBreak => {
-                            inline_content.push_str(&render_node(child));
-                        }
-                        Node::Paragraph(para_children) if *tight => {
-                            // In a tight list item, unwrap first paragraph to inline
-                            let para_content: String =
-                                para_children.iter().map(render_node).collect();
-                            // First paragraph goes on same line as <li>
-                            if inline_content.is_empty() && block_content.is_empty() {
-                                inline_content.push_str(&para_content);
-                            } else {
-                                // Subsequent paragraphs in tight items also unwrapped but as block-level
-                                // Don't add extra newline - content already has it or gets trimmed later
-                                block_content.push_str(&para_content);
-                            }
-                        }
-                        _ => {
-                            block_content.push_str(&render_node(child));
-                        }
+                // Links within the current page need no resolving
+                let (mut href, class) = if page.is_empty() {
+                    (String::new(), "wikilink")
+                } else {
+                    let resolved = match &self.wiki_link_resolver {
+                        Some(resolver) => resolver(page),
+                        None => Some(url_encode(page)),
+                    };
+                    match resolved {
+                        Some(url) => (url, "wikilink"),
+                        None => (url_encode(page), "wikilink wikilink-missing"),
                     }
+                };
+                if let Some(anchor) = anchor {
+                    href.push('#');
+                    href.push_str(&url_encode(anchor));
                 }
 
-                if !inline_content.is_empty() && !block_content.is_empty() {
-                    // Mix of inline and block: inline on same line, blocks indented
+                // From here on a wiki link is treated like any other link
+                let href = self.rewrite_url(UrlKind::Link, &href);
+                format!(
+                    "<a href=\"{}\" class=\"{}\"{}>{}</a>",
+                    escape_html(&href),
+                    class,
+                    self.link_attributes(&href),
+                    content
+                )
+            }
+            Node::Span {
+                children,
+                attributes,
+            } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                format!(
+                    "<span{}>{}</span>",
+                    render_attributes(attributes, None),
+                    content
+                )
+            }
+            Node::Link {
+                destination,
+                title,
+                children,
+                attributes,
+            } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                let destination = self.rewrite_url(UrlKind::Link, destination);
+                let attributes =
+                    render_attributes(attributes, None) + &self.link_attributes(&destination);
+                if let Some(title_text) = title {
                     format!(
-                        "<li>{}\n{}</li>\n",
-                        inline_content.trim_end(),
-                        block_content
+                        "<a href=\"{}\" title=\"{}\"{}>{}</a>",
+                        escape_html(&destination),
+                        escape_html(title_text),
+                        attributes,
+                        content
                     )
-                } else if !block_content.is_empty() {
-                    // Only blocks: newline after <li>
-                    format!("<li>\n{}</li>\n", block_content)
                 } else {
-                    // Only inline (shouldn't happen if has_blocks is true, but handle it)
-                    format!("<li>{}</li>\n", inline_content.trim_end())
+                    format!(
+                        "<a href=\"{}\"{}>{}</a>",
+                        escape_html(&destination),
+                        attributes,
+                        content
+                    )
                 }
-            } else {
-                // Simple inline content only
-                let content: String = children.iter().map(render_node).collect();
-                let trimmed = content.trim_end_matches('\n');
-                format!("<li>{}</li>\n", trimmed)
             }
-        }
-        Node::Text(text) => escape_html(text),
-        Node::Code(code) => format!("<code>{}</code>", escape_html(code)),
-        Node::Emphasis(children) => {
-            let content: String = children.iter().map(render_node).collect();
-            format!("<em>{}</em>", content)
-        }
-        Node::Strong(children) => {
-            let content: String = children.iter().map(render_node).collect();
-            format!("<strong>{}</strong>", content)
-        }
-        Node::Link {
-            destination,
-            title,
-            children,
-        } => {
-            let content: String = children.iter().map(render_node).collect();
-            if let Some(title_text) = title {
+            Node::Image {
+                destination,
+                title,
+                alt_text,
+                attributes,
+            } => {
+                // Convert alt_text nodes to plain text (strip formatting)
+                let alt = alt_text_to_string(alt_text);
+                let destination = self.rewrite_url(UrlKind::Image, destination);
+                let attributes = render_attributes(attributes, None) + self.image_attributes();
+                if let Some(title_text) = title {
+                    format!(
+                        "<img src=\"{}\" alt=\"{}\" title=\"{}\"{} />",
+                        escape_html(&destination),
+                        escape_html(&alt),
+                        escape_html(title_text),
+                        attributes
+                    )
+                } else {
+                    format!(
+                        "<img src=\"{}\" alt=\"{}\"{} />",
+                        escape_html(&destination),
+                        escape_html(&alt),
+                        attributes
+                    )
+                }
+            }
+            Node::Emoji { shortcode, unicode } => match &self.options.emoji_image_url {
+                Some(prefix) => format!(
+                    "<img class=\"emoji\" src=\"{}{}.png\" alt=\"{}\" title=\":{}:\" />",
+                    escape_html(prefix),
+                    emoji::codepoints(unicode),
+                    escape_html(unicode),
+                    escape_html(shortcode)
+                ),
+                None => escape_html(unicode),
+            },
+            Node::Mention(_) | Node::Reference { .. } => {
+                let text = escape_html(&reference_text(node));
+                let url = self
+                    .reference_resolver
+                    .as_ref()
+                    .and_then(|resolver| resolver(node));
+                match url {
+                    Some(url) => {
+                        let class = match node {
+                            Node::Mention(_) => "mention",
+                            Node::Reference {
+                                kind: ReferenceKind::Issue,
+                                ..
+                            } => "issue-reference",
+                            _ => "hashtag",
+                        };
+                        format!(
+                            "<a href=\"{}\" class=\"{}\">{}</a>",
+                            escape_html(&url),
+                            class,
+                            text
+                        )
+                    }
+                    None => text,
+                }
+            }
+            Node::Abbreviation { text, title } => {
                 format!(
-                    "<a href=\"{}\" title=\"{}\">{}</a>",
-                    escape_html(destination),
-                    escape_html(title_text),
-                    content
+                    "<abbr title=\"{}\">{}</abbr>",
+                    escape_html(title),
+                    escape_html(text)
                 )
-            } else {
-                format!("<a href=\"{}\">{}</a>", escape_html(destination), content)
             }
-        }
-        Node::Image {
-            destination,
-            title,
-            alt_text,
-        } => {
-            // Convert alt_text nodes to plain text (strip formatting)
-            let alt = alt_text_to_string(alt_text);
-            if let Some(title_text) = title {
+            Node::HardBreak => "<br />\n".to_string(),
+            Node::HtmlBlock(content) => content.clone(), // Pass through raw HTML unchanged
+            Node::LinkReferenceDefinition { .. } => String::new(), // Produces no output
+            Node::HtmlInline(content) => content.clone(), // Pass through raw HTML unchanged
+            // Math is left for KaTeX/MathJax, using their default delimiters
+            Node::InlineMath(math) => {
                 format!(
-                    "<img src=\"{}\" alt=\"{}\" title=\"{}\" />",
-                    escape_html(destination),
-                    escape_html(&alt),
-                    escape_html(title_text)
+                    "<span class=\"math inline\">\\({}\\)</span>",
+                    escape_html(math)
                 )
-            } else {
+            }
+            Node::DisplayMath(math) => {
                 format!(
-                    "<img src=\"{}\" alt=\"{}\" />",
-                    escape_html(destination),
-                    escape_html(&alt)
+                    "<span class=\"math display\">\\[{}\\]</span>",
+                    escape_html(math)
                 )
             }
+            // GFM Tables
+            Node::Table {
+                alignments,
+                children,
+            } => {
+                let mut header_html = String::new();
+                let mut body_html = String::new();
+
+                // First row is header
+                if !children.is_empty() {
+                    header_html = self.render_table_row(&children[0], alignments, true);
+                }
+
+                // Rest are body rows
+                for row in children.iter().skip(1) {
+                    body_html.push_str(&self.render_table_row(row, alignments, false));
+                }
+
+                if body_html.is_empty() {
+                    format!("<table>\n<thead>\n{}</thead>\n</table>\n", header_html)
+                } else {
+                    format!(
+                        "<table>\n<thead>\n{}</thead>\n<tbody>\n{}</tbody>\n</table>\n",
+                        header_html, body_html
+                    )
+                }
+            }
+            Node::TableRow(_) => {
+                // Should be handled by Table rendering
+                String::new()
+            }
+            Node::TableCell { .. } => {
+                // Should be handled by Table rendering
+                String::new()
+            }
         }
-        Node::HardBreak => "<br />\n".to_string(),
-        Node::HtmlBlock(content) => content.clone(), // Pass through raw HTML unchanged
-        Node::HtmlInline(content) => content.clone(), // Pass through raw HTML unchanged
-        // GFM Tables
-        Node::Table {
-            alignments,
-            children,
-        } => {
-            let mut header_html = String::new();
-            let mut body_html = String::new();
-
-            // First row is header
-            if !children.is_empty() {
-                header_html = render_table_row(&children[0], alignments, true);
+    }
+
+    /// Render a list item as `<tag>`, unwrapping paragraphs when tight
+    fn render_list_item(&self, tag: &str, tight: bool, children: &[Node]) -> String {
+        // Definitions render as nothing; skip them so they don't affect the layout below
+        let children: Vec<&Node> = children
+            .iter()
+            .filter(|child| !matches!(child, Node::LinkReferenceDefinition { .. }))
+            .collect();
+
+        // Determine if this item should render its paragraphs with <p> tags
+        // If tight is true, single paragraphs are unwrapped
+
+        // Check if we have a mix of inline and block content
+        let has_blocks = children.iter().any(|child| {
+            matches!(
+                child,
+                Node::Paragraph(_)
+                    | Node::BlockQuote(_)
+                    | Node::Alert { .. }
+                    | Node::Container { .. }
+                    | Node::CodeBlock { .. }
+                    | Node::MathBlock(_)
+                    | Node::UnorderedList { .. }
+                    | Node::OrderedList { .. }
+                    | Node::DefinitionList(_)
+                    | Node::ThematicBreak
+                    | Node::HtmlBlock(_)
+            )
+        });
+
+        if tight && children.len() == 1 {
+            // Tight item with single child - unwrap paragraph if it's the only content
+            match children[0] {
+                Node::Paragraph(para_children) => {
+                    let content: String = para_children
+                        .iter()
+                        .map(|child| self.render_node(child))
+                        .collect();
+                    return format!("<{}>{}</{}>\n", tag, content.trim_end(), tag);
+                }
+                _ => {
+                    // Single non-paragraph block
+                    let content = self.render_node(children[0]);
+                    if content.ends_with('\n') {
+                        return format!("<{}>\n{}</{}>\n", tag, content, tag);
+                    } else {
+                        return format!("<{}>{}</{}>\n", tag, content, tag);
+                    }
+                }
             }
+        }
 
-            // Rest are body rows
-            for row in children.iter().skip(1) {
-                body_html.push_str(&render_table_row(row, alignments, false));
+        if has_blocks {
+            // Render inline elements first (if any) on the same line as the opening tag
+            let mut inline_content = String::new();
+            let mut block_content = String::new();
+
+            for &child in &children {
+                match child {
+                    Node::Text(_)
+                    | Node::Code(_)
+                    | Node::Emphasis(_)
+                    | Node::Strong(_)
+                    | Node::Superscript(_)
+                    | Node::Subscript(_)
+                    | Node::Mark(_)
+                    | Node::Insert(_)
+                    | Node::Span { .. }
+                    | Node::WikiLink { .. }
+                    | Node::Link { .. }
+                    | Node::Image { .. }
+                    | Node::HtmlInline(_)
+                    | Node::InlineMath(_)
+                    | Node::DisplayMath(_)
+                    | Node::Emoji { .. }
+                    | Node::Mention(_)
+                    | Node::Reference { .. }
+                    | Node::Abbreviation { .. }
+                    | Node::HardBreak => {
+                        inline_content.push_str(&self.render_node(child));
+                    }
+                    Node::Paragraph(para_children) if tight => {
+                        // In a tight list item, unwrap first paragraph to inline
+                        let para_content: String = para_children
+                            .iter()
+                            .map(|child| self.render_node(child))
+                            .collect();
+                        // First paragraph goes on same line as the opening tag
+                        if inline_content.is_empty() && block_content.is_empty() {
+                            inline_content.push_str(&para_content);
+                        } else {
+                            // Subsequent paragraphs in tight items also unwrapped but as block-level
+                            // Don't add extra newline - content already has it or gets trimmed later
+                            block_content.push_str(&para_content);
+                        }
+                    }
+                    _ => {
+                        block_content.push_str(&self.render_node(child));
+                    }
+                }
             }
 
-            if body_html.is_empty() {
-                format!("<table>\n<thead>\n{}</thead>\n</table>\n", header_html)
-            } else {
+            if !inline_content.is_empty() && !block_content.is_empty() {
+                // Mix of inline and block: inline on same line, blocks indented
                 format!(
-                    "<table>\n<thead>\n{}</thead>\n<tbody>\n{}</tbody>\n</table>\n",
-                    header_html, body_html
+                    "<{}>{}\n{}</{}>\n",
+                    tag,
+                    inline_content.trim_end(),
+                    block_content,
+                    tag
                 )
+            } else if !block_content.is_empty() {
+                // Only blocks: newline after the opening tag
+                format!("<{}>\n{}</{}>\n", tag, block_content, tag)
+            } else {
+                // Only inline (shouldn't happen if has_blocks is true, but handle it)
+                format!("<{}>{}</{}>\n", tag, inline_content.trim_end(), tag)
             }
+        } else {
+            // Simple inline content only
+            let content: String = children
+                .iter()
+                .map(|child| self.render_node(child))
+                .collect();
+            let trimmed = content.trim_end_matches('\n');
+            format!("<{}>{}</{}>\n", tag, trimmed, tag)
         }
-        Node::TableRow(_) => {
-            // Should be handled by Table rendering
-            String::new()
+    }
+
+    fn render_table_row(&self, node: &Node, alignments: &[Alignment], is_header: bool) -> String {
+        match node {
+            Node::TableRow(cells) => {
+                let cells_html: String = cells
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        self.render_table_cell(
+                            cell,
+                            alignments.get(i).unwrap_or(&Alignment::None),
+                            is_header,
+                        )
+                    })
+                    .collect();
+                format!("<tr>\n{}</tr>\n", cells_html)
+            }
+            _ => String::new(),
         }
-        Node::TableCell { .. } => {
-            // Should be handled by Table rendering
-            String::new()
+    }
+
+    fn render_table_cell(&self, node: &Node, alignment: &Alignment, is_header: bool) -> String {
+        match node {
+            Node::TableCell { children, .. } => {
+                let content: String = children
+                    .iter()
+                    .map(|child| self.render_node(child))
+                    .collect();
+                let tag = if is_header { "th" } else { "td" };
+
+                match alignment {
+                    Alignment::Left => format!("<{} align=\"left\">{}</{}>\n", tag, content, tag),
+                    Alignment::Right => format!("<{} align=\"right\">{}</{}>\n", tag, content, tag),
+                    Alignment::Center => {
+                        format!("<{} align=\"center\">{}</{}>\n", tag, content, tag)
+                    }
+                    Alignment::None => format!("<{}>{}</{}>\n", tag, content, tag),
+                }
+            }
+            _ => String::new(),
         }
     }
 }
 
-fn render_table_row(node: &Node, alignments: &[Alignment], is_header: bool) -> String {
-    match node {
-        Node::TableRow(cells) => {
-            let cells_html: String = cells
-                .iter()
-                .enumerate()
-                .map(|(i, cell)| {
-                    render_table_cell(
-                        cell,
-                        alignments.get(i).unwrap_or(&Alignment::None),
-                        is_header,
-                    )
-                })
-                .collect();
-            format!("<tr>\n{}</tr>\n", cells_html)
-        }
-        _ => String::new(),
+impl Default for HtmlRenderer {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-fn render_table_cell(node: &Node, alignment: &Alignment, is_header: bool) -> String {
-    match node {
-        Node::TableCell { children, .. } => {
-            let content: String = children.iter().map(render_node).collect();
-            let tag = if is_header { "th" } else { "td" };
-
-            match alignment {
-                Alignment::Left => format!("<{} align=\"left\">{}</{}>\n", tag, content, tag),
-                Alignment::Right => format!("<{} align=\"right\">{}</{}>\n", tag, content, tag),
-                Alignment::Center => format!("<{} align=\"center\">{}</{}>\n", tag, content, tag),
-                Alignment::None => format!("<{}>{}</{}>\n", tag, content, tag),
+/// Host of an absolute (`scheme://host/...`) or protocol-relative (`//host/...`) URL
+fn url_host(url: &str) -> Option<String> {
+    let rest = match url.strip_prefix("//") {
+        Some(rest) => rest,
+        None => {
+            let (scheme, rest) = url.split_once("://")?;
+            let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            if !valid_scheme {
+                return None;
             }
+            rest
         }
-        _ => String::new(),
-    }
+    };
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit('@').next().unwrap_or_default();
+    let host = host_port.split(':').next().unwrap_or_default();
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
 }
 
 fn escape_html(text: &str) -> String {
@@ -296,19 +739,195 @@ fn escape_html(text: &str) -> String {
         .collect()
 }
 
-/// Convert inline nodes to plain text (for image alt text)
-/// This strips all formatting and just keeps the text content
-fn alt_text_to_string(nodes: &[Node]) -> String {
-    nodes
-        .iter()
-        .map(|node| match node {
-            Node::Text(text) => text.clone(),
-            Node::Code(code) => code.clone(),
-            Node::Emphasis(children) | Node::Strong(children) => alt_text_to_string(children),
-            Node::Link { children, .. } => alt_text_to_string(children),
-            Node::Image { alt_text, .. } => alt_text_to_string(alt_text),
-            Node::HardBreak => "\n".to_string(),
-            _ => String::new(),
-        })
-        .collect()
+/// Render attributes from a `{#id .class}` block as HTML attributes with a leading
+/// space. `class` (such as a language class) goes before the block's own classes.
+fn render_attributes(attributes: &Attributes, class: Option<&str>) -> String {
+    let mut html = String::new();
+    if let Some(id) = &attributes.id {
+        html.push_str(&format!(" id=\"{}\"", escape_html(id)));
+    }
+    let classes: Vec<&str> = class
+        .into_iter()
+        .chain(attributes.classes.iter().map(String::as_str))
+        .collect();
+    if !classes.is_empty() {
+        html.push_str(&format!(" class=\"{}\"", escape_html(&classes.join(" "))));
+    }
+    for (key, value) in &attributes.pairs {
+        html.push_str(&format!(" {}=\"{}\"", escape_html(key), escape_html(value)));
+    }
+    html
+}
+
+/// The source text of a `Node::Mention` or `Node::Reference`
+pub(crate) fn reference_text(node: &Node) -> String {
+    match node {
+        Node::Mention(username) => format!("@{}", username),
+        Node::Reference {
+            repository: Some(repository),
+            id,
+            ..
+        } => format!("{}#{}", repository, id),
+        Node::Reference { id, .. } => format!("#{}", id),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Parser, ParserOptions};
+
+    #[test]
+    fn test_external_link_attributes() {
+        let renderer = HtmlRenderer::with_options(RendererOptions {
+            site_host: Some("forum.example.com".to_string()),
+            nofollow_external_links: true,
+            external_links_new_tab: true,
+            ..Default::default()
+        });
+
+        let ast = Parser::new().parse(
+            "[a](https://other.org/x) [b](https://Forum.Example.com:443/t/1) [c](/local) \
+             [d](//cdn.net) [e](mailto:me@x.org)\n",
+        );
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"https://other.org/x\" rel=\"nofollow noopener\" target=\"_blank\">a</a> \
+             <a href=\"https://Forum.Example.com:443/t/1\">b</a> <a href=\"/local\">c</a> \
+             <a href=\"//cdn.net\" rel=\"nofollow noopener\" target=\"_blank\">d</a> \
+             <a href=\"mailto:me@x.org\">e</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_lazy_images() {
+        let renderer = HtmlRenderer::with_options(RendererOptions {
+            lazy_images: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            renderer.render(&Parser::new().parse("![i](/i.png)\n")),
+            "<p><img src=\"/i.png\" alt=\"i\" loading=\"lazy\" decoding=\"async\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_container_renderer() {
+        let options = ParserOptions {
+            containers: true,
+            ..Default::default()
+        };
+        let ast = Parser::with_options(options).parse("::: details More info\nhidden\n:::\n");
+
+        let mut renderer = HtmlRenderer::new();
+        renderer.set_container_renderer("details", |args, content| {
+            format!(
+                "<details>\n<summary>{}</summary>\n{}</details>\n",
+                args, content
+            )
+        });
+        assert_eq!(
+            renderer.render(&ast),
+            "<details>\n<summary>More info</summary>\n<p>hidden</p>\n</details>\n"
+        );
+    }
+
+    #[test]
+    fn test_emoji_images() {
+        let options = ParserOptions {
+            emoji: true,
+            ..Default::default()
+        };
+        let renderer = HtmlRenderer::with_options(RendererOptions {
+            emoji_image_url: Some("/emoji/".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            renderer.render(&Parser::with_options(options).parse(":tada: :hash:\n")),
+            "<p><img class=\"emoji\" src=\"/emoji/1f389.png\" alt=\"🎉\" title=\":tada:\" /> \
+             <img class=\"emoji\" src=\"/emoji/0023-20e3.png\" alt=\"#\u{FE0F}\u{20E3}\" title=\":hash:\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_wiki_link_resolver() {
+        let options = ParserOptions {
+            wiki_links: true,
+            ..Default::default()
+        };
+        let ast = Parser::with_options(options)
+            .parse("[[Home]] [[Setup Guide#Install|steps]] [[#Notes]] [[Gone]]\n");
+
+        let mut renderer = HtmlRenderer::new();
+        renderer.set_wiki_link_resolver(|page| {
+            (page != "Gone").then(|| format!("/wiki/{}", page.to_lowercase().replace(' ', "-")))
+        });
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"/wiki/home\" class=\"wikilink\">Home</a> \
+             <a href=\"/wiki/setup-guide#Install\" class=\"wikilink\">steps</a> \
+             <a href=\"#Notes\" class=\"wikilink\">#Notes</a> \
+             <a href=\"Gone\" class=\"wikilink wikilink-missing\">Gone</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_wiki_links_are_links() {
+        let options = ParserOptions {
+            wiki_links: true,
+            ..Default::default()
+        };
+        let ast = Parser::with_options(options).parse("[[Café Menu#Crème brûlée]] [[Old]]\n");
+
+        // Page names are percent-encoded, then rewritten like other links
+        let mut renderer = HtmlRenderer::with_options(RendererOptions {
+            nofollow_external_links: true,
+            ..Default::default()
+        });
+        renderer.set_wiki_link_resolver(|page| match page {
+            "Old" => Some("https://old.example.com/Old".to_string()),
+            _ => None,
+        });
+        renderer.set_url_rewriter(|_, url| format!("{}?v=1", url));
+        assert_eq!(
+            renderer.render(&ast),
+            "<p><a href=\"Caf%C3%A9%20Menu#Cr%C3%A8me%20br%C3%BBl%C3%A9e?v=1\" \
+             class=\"wikilink wikilink-missing\">Café Menu#Crème brûlée</a> \
+             <a href=\"https://old.example.com/Old?v=1\" class=\"wikilink\" rel=\"nofollow\">Old</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_reference_resolver() {
+        let options = ParserOptions {
+            mentions: true,
+            ..Default::default()
+        };
+        let ast = Parser::with_options(options)
+            .parse("Thanks @octo-cat, see #12, rust-lang/rust#1 and *#release*.\n");
+
+        let mut renderer = HtmlRenderer::new();
+        renderer.set_reference_resolver(|node| match node {
+            Node::Mention(user) => Some(format!("/u/{}", user)),
+            Node::Reference {
+                kind: ReferenceKind::Issue,
+                repository,
+                id,
+            } => Some(format!(
+                "/{}/issues/{}",
+                repository.as_deref().unwrap_or("me/app"),
+                id
+            )),
+            Node::Reference { id, .. } => Some(format!("/tags/{}", id)),
+            _ => None,
+        });
+        assert_eq!(
+            renderer.render(&ast),
+            "<p>Thanks <a href=\"/u/octo-cat\" class=\"mention\">@octo-cat</a>, \
+             see <a href=\"/me/app/issues/12\" class=\"issue-reference\">#12</a>, \
+             <a href=\"/rust-lang/rust/issues/1\" class=\"issue-reference\">rust-lang/rust#1</a> \
+             and <em><a href=\"/tags/release\" class=\"hashtag\">#release</a></em>.</p>\n"
+        );
+    }
 }
@@ -0,0 +1,523 @@
+/// ANSI terminal renderer for CommonMark AST
+use crate::ast::Node;
+use crate::ast::{AlertKind, Alignment};
+use crate::plain_text::{PlainTextRenderer, is_block, prefix_lines};
+use unicode_width::UnicodeWidthChar;
+
+// SGR escape sequences as (start, end) pairs
+const BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+const DIM: (&str, &str) = ("\x1b[2m", "\x1b[22m");
+const ITALIC: (&str, &str) = ("\x1b[3m", "\x1b[23m");
+const UNDERLINE: (&str, &str) = ("\x1b[4m", "\x1b[24m");
+const REVERSE: (&str, &str) = ("\x1b[7m", "\x1b[27m");
+const CODE: (&str, &str) = ("\x1b[36m", "\x1b[39m");
+const RESET: &str = "\x1b[0m";
+
+/// Heading colors by level (bold magenta, blue, cyan, green, yellow, white)
+const HEADING_STYLES: [&str; 6] = [
+    "\x1b[1;35m",
+    "\x1b[1;34m",
+    "\x1b[1;36m",
+    "\x1b[1;32m",
+    "\x1b[1;33m",
+    "\x1b[1;37m",
+];
+
+/// Options controlling the terminal renderer
+#[derive(Debug, Clone)]
+pub struct TerminalOptions {
+    /// Column at which paragraphs are wrapped (code blocks and tables are not wrapped)
+    pub width: usize,
+    /// Emit links as OSC 8 hyperlinks; otherwise the URL follows the link text
+    pub hyperlinks: bool,
+}
+
+impl Default for TerminalOptions {
+    fn default() -> Self {
+        TerminalOptions {
+            width: 80,
+            hyperlinks: true,
+        }
+    }
+}
+
+/// Renders a tree for display in an ANSI terminal
+pub struct TerminalRenderer {
+    options: TerminalOptions,
+    plain: PlainTextRenderer,
+}
+
+impl TerminalRenderer {
+    pub fn new() -> Self {
+        Self::with_options(TerminalOptions::default())
+    }
+
+    pub fn with_options(options: TerminalOptions) -> Self {
+        TerminalRenderer {
+            options,
+            plain: PlainTextRenderer::new(),
+        }
+    }
+
+    pub fn render(&self, node: &Node) -> String {
+        let width = self.options.width.max(20);
+        let text = match node {
+            Node::Document(children) => self.render_blocks(children, width, false),
+            _ => self.render_blocks(std::slice::from_ref(node), width, false),
+        };
+        if text.is_empty() { text } else { text + "\n" }
+    }
+
+    /// Render inline nodes on one line; soft line breaks become spaces and hard breaks
+    /// newlines
+    fn render_inline(&self, nodes: &[Node]) -> String {
+        nodes
+            .iter()
+            .map(|node| self.render_inline_node(node))
+            .collect()
+    }
+
+    fn render_inline_node(&self, node: &Node) -> String {
+        let styled = |(start, end): (&str, &str), children: &[Node]| {
+            format!("{}{}{}", start, self.render_inline(children), end)
+        };
+        match node {
+            Node::Text(text) => strip_controls(text).replace('\n', " "),
+            Node::Code(code) => format!("{}{}{}", CODE.0, strip_controls(code), CODE.1),
+            Node::Emphasis(children) => styled(ITALIC, children),
+            Node::Strong(children) => styled(BOLD, children),
+            Node::Insert(children) => styled(UNDERLINE, children),
+            Node::Mark(children) => styled(REVERSE, children),
+            Node::Link {
+                destination,
+                children,
+                ..
+            } => {
+                let text = styled(UNDERLINE, children);
+                let destination = strip_controls(destination);
+                if self.options.hyperlinks {
+                    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", destination, text)
+                } else if self.plain.render_inline(children) == destination {
+                    text
+                } else {
+                    format!("{} ({})", text, destination)
+                }
+            }
+            Node::Image { alt_text, .. } => {
+                format!(
+                    "[image: {}]",
+                    strip_controls(&self.plain.render_inline(alt_text))
+                )
+            }
+            Node::HardBreak => "\n".to_string(),
+            Node::Superscript(children)
+            | Node::Subscript(children)
+            | Node::WikiLink { children, .. }
+            | Node::Span { children, .. } => self.render_inline(children),
+            // Math, emoji, mentions and the like read fine as plain text
+            _ => strip_controls(&self.plain.render_inline(std::slice::from_ref(node)))
+                .replace('\n', " "),
+        }
+    }
+
+    /// Render a sequence of blocks to fit in `width` columns, separated by a newline if
+    /// `tight`, else a blank line
+    fn render_blocks(&self, nodes: &[Node], width: usize, tight: bool) -> String {
+        let mut blocks = Vec::new();
+        let mut inline = Vec::new();
+
+        for node in nodes {
+            if is_block(node) {
+                if !inline.is_empty() {
+                    blocks.push(wrap(&self.render_inline(&inline), width));
+                    inline.clear();
+                }
+                blocks.push(self.render_block(node, width));
+            } else {
+                inline.push(node.clone());
+            }
+        }
+        if !inline.is_empty() {
+            blocks.push(wrap(&self.render_inline(&inline), width));
+        }
+
+        blocks.retain(|block| !block.is_empty());
+        blocks.join(if tight { "\n" } else { "\n\n" })
+    }
+
+    fn render_block(&self, node: &Node, width: usize) -> String {
+        match node {
+            Node::Document(children) | Node::Container { children, .. } => {
+                self.render_blocks(children, width, false)
+            }
+            Node::Paragraph(children) => wrap(&self.render_inline(children), width),
+            Node::Heading {
+                level, children, ..
+            } => {
+                let style = HEADING_STYLES[(*level as usize).clamp(1, 6) - 1];
+                let text = wrap(&self.render_inline(children), width);
+                // Nested styles end with partial resets, so restyle every line
+                prefix_lines(&text, style, style)
+                    .lines()
+                    .map(|line| format!("{}{}", line, RESET))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Node::CodeBlock { info, literal, .. } => {
+                code_box(&strip_controls(info), &strip_controls(literal))
+            }
+            Node::MathBlock(math) => code_box("math", &strip_controls(math)),
+            Node::ThematicBreak => format!("{}{}{}", DIM.0, "─".repeat(width), DIM.1),
+            Node::BlockQuote(children) => {
+                let bar = format!("{}│{} ", DIM.0, DIM.1);
+                let content = self.render_blocks(children, width.saturating_sub(2), false);
+                prefix_lines(&content, &bar, &bar)
+            }
+            Node::Alert { kind, children } => {
+                let color = alert_color(*kind);
+                let bar = format!("{}│\x1b[39m ", color);
+                let title = format!("{}{}{}{}", BOLD.0, color, kind.title(), RESET);
+                let content = self.render_blocks(children, width.saturating_sub(2), false);
+                prefix_lines(&format!("{}\n{}", title, content), &bar, &bar)
+            }
+            Node::UnorderedList { tight, children } => {
+                let items: Vec<String> = children
+                    .iter()
+                    .map(|item| self.render_list_item(item, "• ", width))
+                    .collect();
+                items.join(if *tight { "\n" } else { "\n\n" })
+            }
+            Node::OrderedList {
+                start,
+                tight,
+                children,
+            } => {
+                let items: Vec<String> = children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let marker = format!("{}. ", *start as usize + i);
+                        self.render_list_item(item, &marker, width)
+                    })
+                    .collect();
+                items.join(if *tight { "\n" } else { "\n\n" })
+            }
+            Node::ListItem { .. } => self.render_list_item(node, "• ", width),
+            Node::DefinitionList(children) => {
+                let parts: Vec<String> = children
+                    .iter()
+                    .map(|child| match child {
+                        Node::DefinitionTerm(term) => {
+                            format!("{}{}{}", BOLD.0, self.render_inline(term), BOLD.1)
+                        }
+                        Node::DefinitionDetails { tight, children } => {
+                            let content =
+                                self.render_blocks(children, width.saturating_sub(4), *tight);
+                            prefix_lines(&content, "    ", "    ")
+                        }
+                        _ => self.render_block(child, width),
+                    })
+                    .collect();
+                parts.join("\n")
+            }
+            Node::Table {
+                alignments,
+                children,
+            } => self.render_table(alignments, children),
+            // Raw HTML and metadata aren't shown
+            Node::HtmlBlock(_)
+            | Node::FrontMatter { .. }
+            | Node::LinkReferenceDefinition { .. } => String::new(),
+            _ => wrap(&self.render_inline_node(node), width),
+        }
+    }
+
+    fn render_list_item(&self, item: &Node, marker: &str, width: usize) -> String {
+        let indent = marker.chars().count();
+        let content = match item {
+            Node::ListItem { tight, children } => {
+                self.render_blocks(children, width.saturating_sub(indent), *tight)
+            }
+            _ => self.render_block(item, width.saturating_sub(indent)),
+        };
+        prefix_lines(&content, marker, &" ".repeat(indent))
+    }
+
+    /// Render a table with box-drawing borders and a bold header row
+    fn render_table(&self, alignments: &[Alignment], rows: &[Node]) -> String {
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| match row {
+                Node::TableRow(cells) => cells
+                    .iter()
+                    .map(|cell| match cell {
+                        Node::TableCell { children, .. } => {
+                            self.render_inline(children).replace('\n', " ")
+                        }
+                        _ => String::new(),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| visible_width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let border = |left: &str, middle: &str, right: &str| {
+            let segments: Vec<String> = widths.iter().map(|&w| "─".repeat(w + 2)).collect();
+            format!("{}{}{}", left, segments.join(middle), right)
+        };
+        let format_row = |row: &[String], is_header: bool| {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, &width)| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or("");
+                    let cell = if is_header {
+                        format!("{}{}{}", BOLD.0, cell, BOLD.1)
+                    } else {
+                        cell.to_string()
+                    };
+                    let alignment = alignments.get(column).unwrap_or(&Alignment::None);
+                    format!(" {} ", pad(&cell, width, alignment))
+                })
+                .collect();
+            format!("│{}│", cells.join("│"))
+        };
+
+        let mut lines = vec![border("┌", "┬", "┐")];
+        for (i, row) in rows.iter().enumerate() {
+            lines.push(format_row(row, i == 0));
+            if i == 0 && rows.len() > 1 {
+                lines.push(border("├", "┼", "┤"));
+            }
+        }
+        lines.push(border("└", "┴", "┘"));
+        lines.join("\n")
+    }
+}
+
+impl Default for TerminalRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn alert_color(kind: AlertKind) -> &'static str {
+    match kind {
+        AlertKind::Note => "\x1b[34m",
+        AlertKind::Tip => "\x1b[32m",
+        AlertKind::Important => "\x1b[35m",
+        AlertKind::Warning => "\x1b[33m",
+        AlertKind::Caution => "\x1b[31m",
+    }
+}
+
+/// Drop control characters other than newlines and tabs, so document text can't
+/// emit escape sequences of its own
+fn strip_controls(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !c.is_control() || c == '\n' || c == '\t')
+        .collect()
+}
+
+/// Draw a box around a code block, with the info string in the top border
+fn code_box(info: &str, literal: &str) -> String {
+    let lines: Vec<String> = literal
+        .trim_end_matches('\n')
+        .split('\n')
+        .map(|line| line.replace('\t', "    "))
+        .collect();
+    let label = if info.is_empty() {
+        String::new()
+    } else {
+        format!(" {} ", info)
+    };
+    let inner = lines
+        .iter()
+        .map(|line| visible_width(line))
+        .max()
+        .unwrap_or(0)
+        .max(visible_width(&label) + 1);
+
+    let mut result = vec![format!(
+        "┌─{}{}┐",
+        label,
+        "─".repeat(inner + 1 - visible_width(&label))
+    )];
+    for line in &lines {
+        result.push(format!("│ {} │", pad(line, inner, &Alignment::Left)));
+    }
+    result.push(format!("└{}┘", "─".repeat(inner + 2)));
+    result.join("\n")
+}
+
+/// Pad `text` to `width` visible columns
+fn pad(text: &str, width: usize, alignment: &Alignment) -> String {
+    let padding = width.saturating_sub(visible_width(text));
+    match alignment {
+        Alignment::Right => format!("{}{}", " ".repeat(padding), text),
+        Alignment::Center => format!(
+            "{}{}{}",
+            " ".repeat(padding / 2),
+            text,
+            " ".repeat(padding - padding / 2)
+        ),
+        Alignment::Left | Alignment::None => format!("{}{}", text, " ".repeat(padding)),
+    }
+}
+
+/// Number of columns `text` takes up, counting wide characters twice and not counting
+/// escape sequences
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            width += c.width().unwrap_or(0);
+            continue;
+        }
+        match chars.next() {
+            // CSI: ends with a byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ends with BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    width
+}
+
+/// Greedily wrap text at spaces so lines fit in `width` columns; existing newlines are
+/// kept and words longer than the width get a line of their own
+fn wrap(text: &str, width: usize) -> String {
+    let mut lines = Vec::new();
+    for paragraph_line in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in paragraph_line.split(' ').filter(|word| !word.is_empty()) {
+            let word_width = visible_width(word);
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn render(input: &str) -> String {
+        TerminalRenderer::with_options(TerminalOptions {
+            width: 20,
+            ..Default::default()
+        })
+        .render(&Parser::new().parse(input))
+    }
+
+    #[test]
+    fn test_terminal_styles() {
+        assert_eq!(render("# Title\n"), "\x1b[1;35mTitle\x1b[0m\n");
+        assert_eq!(
+            render("**bold** *it* `code`\n"),
+            "\x1b[1mbold\x1b[22m \x1b[3mit\x1b[23m \x1b[36mcode\x1b[39m\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_wrapping() {
+        assert_eq!(
+            render("one two three four five six seven\n"),
+            "one two three four\nfive six seven\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_hyperlinks() {
+        assert_eq!(
+            render("[x](https://x.org)\n"),
+            "\x1b]8;;https://x.org\x1b\\\x1b[4mx\x1b[24m\x1b]8;;\x1b\\\n"
+        );
+
+        let plain = TerminalRenderer::with_options(TerminalOptions {
+            hyperlinks: false,
+            ..Default::default()
+        });
+        assert_eq!(
+            plain.render(&Parser::new().parse("[x](https://x.org)\n")),
+            "\x1b[4mx\x1b[24m (https://x.org)\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_boxes() {
+        assert_eq!(
+            render("```rs\nfn f() {}\n```\n"),
+            "┌─ rs ──────┐\n│ fn f() {} │\n└───────────┘\n"
+        );
+        assert_eq!(
+            render("| A | B |\n|---|--:|\n| 1 | 22 |\n"),
+            "┌───┬────┐\n\
+             │ \x1b[1mA\x1b[22m │  \x1b[1mB\x1b[22m │\n\
+             ├───┼────┤\n\
+             │ 1 │ 22 │\n\
+             └───┴────┘\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_wide_characters() {
+        assert_eq!(
+            render("| A | B |\n|---|---|\n| 漢字 | 🎉 |\n"),
+            "┌──────┬────┐\n\
+             │ \x1b[1mA\x1b[22m    │ \x1b[1mB\x1b[22m  │\n\
+             ├──────┼────┤\n\
+             │ 漢字 │ 🎉 │\n\
+             └──────┴────┘\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_strips_control_characters() {
+        assert_eq!(
+            render("a\x1b[31mb `c\x07d`\n"),
+            "a[31mb \x1b[36mcd\x1b[39m\n"
+        );
+        assert_eq!(
+            render("```\n\x1b]0;x\x07\tz\n```\n"),
+            "┌───────────┐\n│ ]0;x    z │\n└───────────┘\n"
+        );
+    }
+}
@@ -0,0 +1,430 @@
+/// CommonMark XML renderer for CommonMark AST
+use crate::ast::Node;
+use crate::ast::{Alignment, Attributes, ReferenceKind};
+
+/// Renders a tree as XML following `CommonMark.dtd`, matching the output of
+/// `cmark -t xml`. Extension nodes use elements of their own named in the same
+/// style (`table`, `alert`, `wiki_link`, ...). Ordered lists have no `delim`
+/// attribute since the tree doesn't record the delimiter.
+pub struct XmlRenderer;
+
+impl XmlRenderer {
+    pub fn new() -> Self {
+        XmlRenderer
+    }
+
+    pub fn render(&self, node: &Node) -> String {
+        let mut output = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE document SYSTEM \"CommonMark.dtd\">\n",
+        );
+        match node {
+            Node::Document(children) => {
+                if children.is_empty() {
+                    output.push_str("<document xmlns=\"http://commonmark.org/xml/1.0\" />\n");
+                } else {
+                    output.push_str("<document xmlns=\"http://commonmark.org/xml/1.0\">\n");
+                    self.render_children(children, 1, &mut output);
+                    output.push_str("</document>\n");
+                }
+            }
+            _ => self.render_node(node, 0, &mut output),
+        }
+        output
+    }
+
+    fn render_children(&self, children: &[Node], depth: usize, output: &mut String) {
+        for child in merge_text(children) {
+            self.render_node(&child, depth, output);
+        }
+    }
+
+    /// Write an element with its children, or an empty element when there are none
+    fn element(
+        &self,
+        output: &mut String,
+        depth: usize,
+        name: &str,
+        attrs: &[(&str, String)],
+        children: &[Node],
+    ) {
+        let indent = "  ".repeat(depth);
+        let attrs = format_attributes(attrs);
+        if children.is_empty() {
+            output.push_str(&format!("{}<{}{} />\n", indent, name, attrs));
+        } else {
+            output.push_str(&format!("{}<{}{}>\n", indent, name, attrs));
+            self.render_children(children, depth + 1, output);
+            output.push_str(&format!("{}</{}>\n", indent, name));
+        }
+    }
+
+    fn render_node(&self, node: &Node, depth: usize, output: &mut String) {
+        match node {
+            Node::Document(children) => self.element(output, depth, "document", &[], children),
+            Node::FrontMatter { format, raw } => {
+                let format = format!("{:?}", format).to_lowercase();
+                literal(output, depth, "front_matter", &[("format", format)], raw)
+            }
+            Node::Paragraph(children) => self.element(output, depth, "paragraph", &[], children),
+            Node::Heading {
+                level,
+                children,
+                attributes,
+            } => {
+                let mut attrs = vec![("level", level.to_string())];
+                attrs.extend(html_attributes(attributes));
+                self.element(output, depth, "heading", &attrs, children)
+            }
+            Node::CodeBlock {
+                info,
+                literal: code,
+                attributes,
+            } => {
+                let mut attrs = Vec::new();
+                if !info.is_empty() {
+                    attrs.push(("info", info.clone()));
+                }
+                attrs.extend(html_attributes(attributes));
+                literal(output, depth, "code_block", &attrs, code)
+            }
+            Node::MathBlock(math) => literal(output, depth, "math_block", &[], math),
+            Node::ThematicBreak => self.element(output, depth, "thematic_break", &[], &[]),
+            Node::BlockQuote(children) => self.element(output, depth, "block_quote", &[], children),
+            Node::Alert { kind, children } => self.element(
+                output,
+                depth,
+                "alert",
+                &[("kind", kind.name().to_string())],
+                children,
+            ),
+            Node::Container {
+                name,
+                args,
+                children,
+            } => {
+                let mut attrs = vec![("name", name.clone())];
+                if !args.is_empty() {
+                    attrs.push(("args", args.clone()));
+                }
+                self.element(output, depth, "container", &attrs, children)
+            }
+            Node::UnorderedList { tight, children } => self.element(
+                output,
+                depth,
+                "list",
+                &[("type", "bullet".to_string()), ("tight", tight.to_string())],
+                children,
+            ),
+            Node::OrderedList {
+                start,
+                tight,
+                children,
+            } => self.element(
+                output,
+                depth,
+                "list",
+                &[
+                    ("type", "ordered".to_string()),
+                    ("start", start.to_string()),
+                    ("tight", tight.to_string()),
+                ],
+                children,
+            ),
+            Node::ListItem { children, .. } => self.element(output, depth, "item", &[], children),
+            Node::DefinitionList(children) => {
+                self.element(output, depth, "definition_list", &[], children)
+            }
+            Node::DefinitionTerm(children) => {
+                self.element(output, depth, "definition_term", &[], children)
+            }
+            Node::DefinitionDetails { children, .. } => {
+                self.element(output, depth, "definition_details", &[], children)
+            }
+            // Soft line breaks, as split out by merge_text
+            Node::Text(text) if text == "\n" => self.element(output, depth, "softbreak", &[], &[]),
+            Node::Text(text) => literal(output, depth, "text", &[], text),
+            Node::Code(code) => literal(output, depth, "code", &[], code),
+            Node::Emphasis(children) => self.element(output, depth, "emph", &[], children),
+            Node::Strong(children) => self.element(output, depth, "strong", &[], children),
+            Node::Superscript(children) => {
+                self.element(output, depth, "superscript", &[], children)
+            }
+            Node::Subscript(children) => self.element(output, depth, "subscript", &[], children),
+            Node::Mark(children) => self.element(output, depth, "mark", &[], children),
+            Node::Insert(children) => self.element(output, depth, "insert", &[], children),
+            Node::Link {
+                destination,
+                title,
+                children,
+                attributes,
+            } => {
+                let mut attrs = vec![
+                    ("destination", destination.clone()),
+                    ("title", title.clone().unwrap_or_default()),
+                ];
+                attrs.extend(html_attributes(attributes));
+                self.element(output, depth, "link", &attrs, children)
+            }
+            Node::Image {
+                destination,
+                title,
+                alt_text,
+                attributes,
+            } => {
+                let mut attrs = vec![
+                    ("destination", destination.clone()),
+                    ("title", title.clone().unwrap_or_default()),
+                ];
+                attrs.extend(html_attributes(attributes));
+                self.element(output, depth, "image", &attrs, alt_text)
+            }
+            Node::WikiLink {
+                page,
+                anchor,
+                children,
+            } => {
+                let mut attrs = vec![("page", page.clone())];
+                if let Some(anchor) = anchor {
+                    attrs.push(("anchor", anchor.clone()));
+                }
+                self.element(output, depth, "wiki_link", &attrs, children)
+            }
+            Node::Span {
+                children,
+                attributes,
+            } => self.element(
+                output,
+                depth,
+                "span",
+                &html_attributes(attributes),
+                children,
+            ),
+            Node::Emoji { shortcode, unicode } => {
+                let attrs = [("shortcode", shortcode.clone())];
+                literal(output, depth, "emoji", &attrs, unicode)
+            }
+            Node::Mention(username) => self.element(
+                output,
+                depth,
+                "mention",
+                &[("username", username.clone())],
+                &[],
+            ),
+            Node::Reference {
+                kind,
+                repository,
+                id,
+            } => {
+                let kind = match kind {
+                    ReferenceKind::Issue => "issue",
+                    ReferenceKind::Tag => "tag",
+                };
+                let mut attrs = vec![("kind", kind.to_string())];
+                if let Some(repository) = repository {
+                    attrs.push(("repository", repository.clone()));
+                }
+                attrs.push(("id", id.clone()));
+                self.element(output, depth, "reference", &attrs, &[])
+            }
+            Node::Abbreviation { text, title } => {
+                let attrs = [("title", title.clone())];
+                literal(output, depth, "abbreviation", &attrs, text)
+            }
+            Node::HardBreak => self.element(output, depth, "linebreak", &[], &[]),
+            Node::HtmlBlock(html) => literal(output, depth, "html_block", &[], html),
+            Node::LinkReferenceDefinition {
+                label,
+                destination,
+                title,
+            } => self.element(
+                output,
+                depth,
+                "link_reference_definition",
+                &[
+                    ("label", label.clone()),
+                    ("destination", destination.clone()),
+                    ("title", title.clone().unwrap_or_default()),
+                ],
+                &[],
+            ),
+            Node::HtmlInline(html) => literal(output, depth, "html_inline", &[], html),
+            Node::InlineMath(math) => literal(output, depth, "math", &[], math),
+            Node::DisplayMath(math) => literal(output, depth, "display_math", &[], math),
+            Node::Table {
+                alignments,
+                children,
+            } => {
+                let alignments: Vec<&str> = alignments
+                    .iter()
+                    .map(|alignment| match alignment {
+                        Alignment::None => "none",
+                        Alignment::Left => "left",
+                        Alignment::Right => "right",
+                        Alignment::Center => "center",
+                    })
+                    .collect();
+                self.element(
+                    output,
+                    depth,
+                    "table",
+                    &[("alignments", alignments.join(" "))],
+                    children,
+                )
+            }
+            Node::TableRow(children) => {
+                let is_header = matches!(
+                    children.first(),
+                    Some(Node::TableCell {
+                        is_header: true,
+                        ..
+                    })
+                );
+                let name = if is_header {
+                    "table_header"
+                } else {
+                    "table_row"
+                };
+                self.element(output, depth, name, &[], children)
+            }
+            Node::TableCell { children, .. } => {
+                self.element(output, depth, "table_cell", &[], children)
+            }
+        }
+    }
+}
+
+impl Default for XmlRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write an element whose content is literal text, with whitespace preserved
+fn literal(output: &mut String, depth: usize, name: &str, attrs: &[(&str, String)], text: &str) {
+    output.push_str(&format!(
+        "{}<{}{} xml:space=\"preserve\">{}</{}>\n",
+        "  ".repeat(depth),
+        name,
+        format_attributes(attrs),
+        escape_xml(text),
+        name
+    ));
+}
+
+fn format_attributes(attrs: &[(&str, String)]) -> String {
+    attrs
+        .iter()
+        .map(|(name, value)| format!(" {}=\"{}\"", name, escape_xml(value)))
+        .collect()
+}
+
+/// The id and classes of a `{#id .class}` block as `id` and `class` attributes
+fn html_attributes(attributes: &Attributes) -> Vec<(&'static str, String)> {
+    let mut attrs = Vec::new();
+    if let Some(id) = &attributes.id {
+        attrs.push(("id", id.clone()));
+    }
+    if !attributes.classes.is_empty() {
+        attrs.push(("class", attributes.classes.join(" ")));
+    }
+    attrs
+}
+
+/// Merge adjacent text nodes, which the parser leaves split at delimiters, and turn
+/// the newlines of soft line breaks into `softbreak` elements as cmark does
+fn merge_text(nodes: &[Node]) -> Vec<Node> {
+    let mut result = Vec::new();
+    let mut text = String::new();
+    let flush = |text: &mut String, result: &mut Vec<Node>| {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                result.push(Node::Text("\n".to_string()));
+            }
+            if !line.is_empty() {
+                result.push(Node::Text(line.to_string()));
+            }
+        }
+        text.clear();
+    };
+
+    for node in nodes {
+        match node {
+            Node::Text(t) => text.push_str(t),
+            _ => {
+                flush(&mut text, &mut result);
+                result.push(node.clone());
+            }
+        }
+    }
+    flush(&mut text, &mut result);
+    result
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_xml_document() {
+        let ast = Parser::new().parse(
+            "# Hi\n\n\
+             *a* & [b](/u \"t\")\nc\n\n\
+             2. `x`\n\n\
+             ```rust\n<T>\n```\n",
+        );
+        assert_eq!(
+            XmlRenderer::new().render(&ast),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE document SYSTEM \"CommonMark.dtd\">\n\
+             <document xmlns=\"http://commonmark.org/xml/1.0\">\n\
+             \x20 <heading level=\"1\">\n\
+             \x20   <text xml:space=\"preserve\">Hi</text>\n\
+             \x20 </heading>\n\
+             \x20 <paragraph>\n\
+             \x20   <emph>\n\
+             \x20     <text xml:space=\"preserve\">a</text>\n\
+             \x20   </emph>\n\
+             \x20   <text xml:space=\"preserve\"> &amp; </text>\n\
+             \x20   <link destination=\"/u\" title=\"t\">\n\
+             \x20     <text xml:space=\"preserve\">b</text>\n\
+             \x20   </link>\n\
+             \x20   <softbreak />\n\
+             \x20   <text xml:space=\"preserve\">c</text>\n\
+             \x20 </paragraph>\n\
+             \x20 <list type=\"ordered\" start=\"2\" tight=\"true\">\n\
+             \x20   <item>\n\
+             \x20     <paragraph>\n\
+             \x20       <code xml:space=\"preserve\">x</code>\n\
+             \x20     </paragraph>\n\
+             \x20   </item>\n\
+             \x20 </list>\n\
+             \x20 <code_block info=\"rust\" xml:space=\"preserve\">&lt;T&gt;\n</code_block>\n\
+             </document>\n"
+        );
+    }
+
+    #[test]
+    fn test_xml_empty_document() {
+        assert!(
+            XmlRenderer::new()
+                .render(&Parser::new().parse(""))
+                .ends_with("<document xmlns=\"http://commonmark.org/xml/1.0\" />\n")
+        );
+    }
+}
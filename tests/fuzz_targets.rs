@@ -0,0 +1,357 @@
+//! Fuzz targets for `markdown_to_html` and `Parser::parse`.
+//!
+//! Running `cargo test` replays the seed corpus in `tests/data/fuzz_corpus` through the
+//! checks behind both targets. Recording a test-fuzz corpus is slow, so it is an ignored
+//! test; to fuzz, record first and then run the targets:
+//!
+//! ```text
+//! cargo test --test fuzz_targets -- --ignored record_corpus
+//! cargo test-fuzz markdown_to_html_target
+//! cargo test-fuzz parse_target
+//! ```
+//!
+//! The seed corpus is regenerated from the spec examples with
+//! `cargo run --example build_fuzz_corpus`.
+use conformark::ast::Node;
+use conformark::json::{from_json, to_json};
+use conformark::markdown_to_html;
+use conformark::parser::{Parser, ParserOptions};
+use conformark::renderer::HtmlRenderer;
+use std::fs;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Upper bound for a single parse + render; anything slower is treated as a hang
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Elements the renderer emits in self-closing form
+const VOID_ELEMENTS: [&str; 3] = ["br", "hr", "img"];
+
+#[test_fuzz::test_fuzz]
+fn markdown_to_html_target(markdown: &str) {
+    check_markdown_to_html(markdown);
+}
+
+#[test_fuzz::test_fuzz]
+fn parse_target(markdown: &str) {
+    check_parse(markdown);
+}
+
+/// No hang, deterministic output and, for inputs without raw HTML, well-nested tags
+fn check_markdown_to_html(markdown: &str) {
+    let html = with_timeout("markdown_to_html", markdown.to_string(), |markdown| {
+        markdown_to_html(&markdown)
+    });
+
+    assert_eq!(
+        html,
+        markdown_to_html(markdown),
+        "output is not deterministic"
+    );
+
+    // Without '<' in the input there can be no raw HTML or autolinks,
+    // so every tag in the output comes from the renderer itself
+    if !markdown.contains('<') {
+        check_nesting(&html);
+    }
+}
+
+/// No hang, deterministic AST (also on a reused parser) and HTML, and well-nested tags once raw HTML is neutralized
+fn check_parse(markdown: &str) {
+    let ast = with_timeout("Parser::parse", markdown.to_string(), |markdown| {
+        Parser::new().parse(&markdown)
+    });
+
+    assert_eq!(
+        ast,
+        Parser::new().parse(markdown),
+        "AST is not deterministic"
+    );
+
+    // A parser reused after another document must not carry definitions over
+    let mut reused = Parser::new();
+    reused.parse("[foo]: /leaked\n[bar]: /leaked\n");
+    assert_eq!(
+        ast,
+        reused.parse(markdown),
+        "parser state leaked between documents"
+    );
+
+    let renderer = HtmlRenderer::new();
+    let html = renderer.render(&ast);
+    assert_eq!(html, renderer.render(&ast), "render is not deterministic");
+
+    // Raw HTML is passed through verbatim and may be malformed; render it as text instead
+    with_timeout("HtmlRenderer::render", escape_raw_html(ast), |ast| {
+        check_nesting(&HtmlRenderer::new().render(&ast))
+    });
+
+    // The same holds with every optional syntax enabled
+    let ast = with_timeout(
+        "Parser::parse with all extensions",
+        markdown.to_string(),
+        |markdown| Parser::with_options(all_extensions()).parse(&markdown),
+    );
+    let round_trip = with_timeout("JSON round trip", ast.clone(), |ast| {
+        from_json(&to_json(&ast))
+    });
+    assert_eq!(
+        round_trip,
+        Ok(ast.clone()),
+        "JSON format does not round-trip"
+    );
+    with_timeout("HtmlRenderer::render", escape_raw_html(ast), |ast| {
+        check_nesting(&HtmlRenderer::new().render(&ast))
+    });
+}
+
+/// Run `target` on `input` on a thread of its own so that a hang fails the check after
+/// `TIMEOUT` instead of blocking the test forever
+fn with_timeout<I: Send + 'static, T: Send + 'static>(
+    name: &str,
+    input: I,
+    target: fn(I) -> T,
+) -> T {
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let _ = sender.send(target(input));
+    });
+    match receiver.recv_timeout(TIMEOUT) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => panic!("{} did not finish within {:?}", name, TIMEOUT),
+        // The target panicked; report its panic rather than a closed channel
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => unreachable!("target finished without sending a result"),
+        },
+    }
+}
+
+/// Parser options with every optional syntax turned on
+fn all_extensions() -> ParserOptions {
+    ParserOptions {
+        keep_reference_definitions: true,
+        smart: true,
+        math: true,
+        front_matter: true,
+        definition_lists: true,
+        attributes: true,
+        alerts: true,
+        containers: true,
+        superscript: true,
+        subscript: true,
+        highlight: true,
+        insert: true,
+        emoji: true,
+        wiki_links: true,
+        mentions: true,
+        abbreviations: true,
+        heading_ids: true,
+        auto_heading_ids: true,
+    }
+}
+
+/// Replace raw HTML nodes with text so the rendered output only contains renderer tags
+fn escape_raw_html(node: Node) -> Node {
+    let map = |children: Vec<Node>| children.into_iter().map(escape_raw_html).collect();
+    match node {
+        Node::HtmlBlock(html) | Node::HtmlInline(html) => Node::Text(html),
+        Node::Document(children) => Node::Document(map(children)),
+        Node::Paragraph(children) => Node::Paragraph(map(children)),
+        Node::Heading {
+            level,
+            children,
+            attributes,
+        } => Node::Heading {
+            level,
+            children: map(children),
+            attributes,
+        },
+        Node::BlockQuote(children) => Node::BlockQuote(map(children)),
+        Node::Alert { kind, children } => Node::Alert {
+            kind,
+            children: map(children),
+        },
+        Node::Container {
+            name,
+            args,
+            children,
+        } => Node::Container {
+            name,
+            args,
+            children: map(children),
+        },
+        Node::UnorderedList { tight, children } => Node::UnorderedList {
+            tight,
+            children: map(children),
+        },
+        Node::OrderedList {
+            start,
+            tight,
+            children,
+        } => Node::OrderedList {
+            start,
+            tight,
+            children: map(children),
+        },
+        Node::ListItem { tight, children } => Node::ListItem {
+            tight,
+            children: map(children),
+        },
+        Node::DefinitionList(children) => Node::DefinitionList(map(children)),
+        Node::DefinitionTerm(children) => Node::DefinitionTerm(map(children)),
+        Node::DefinitionDetails { tight, children } => Node::DefinitionDetails {
+            tight,
+            children: map(children),
+        },
+        Node::Emphasis(children) => Node::Emphasis(map(children)),
+        Node::Strong(children) => Node::Strong(map(children)),
+        Node::Superscript(children) => Node::Superscript(map(children)),
+        Node::Subscript(children) => Node::Subscript(map(children)),
+        Node::Mark(children) => Node::Mark(map(children)),
+        Node::Insert(children) => Node::Insert(map(children)),
+        Node::WikiLink {
+            page,
+            anchor,
+            children,
+        } => Node::WikiLink {
+            page,
+            anchor,
+            children: map(children),
+        },
+        Node::Span {
+            children,
+            attributes,
+        } => Node::Span {
+            children: map(children),
+            attributes,
+        },
+        Node::Link {
+            destination,
+            title,
+            children,
+            attributes,
+        } => Node::Link {
+            destination,
+            title,
+            children: map(children),
+            attributes,
+        },
+        Node::Image {
+            destination,
+            title,
+            alt_text,
+            attributes,
+        } => Node::Image {
+            destination,
+            title,
+            alt_text: map(alt_text),
+            attributes,
+        },
+        Node::Table {
+            alignments,
+            children,
+        } => Node::Table {
+            alignments,
+            children: map(children),
+        },
+        Node::TableRow(children) => Node::TableRow(map(children)),
+        Node::TableCell {
+            is_header,
+            children,
+        } => Node::TableCell {
+            is_header,
+            children: map(children),
+        },
+        Node::Text(_)
+        | Node::Code(_)
+        | Node::CodeBlock { .. }
+        | Node::MathBlock(_)
+        | Node::ThematicBreak
+        | Node::HardBreak
+        | Node::Emoji { .. }
+        | Node::Mention(_)
+        | Node::Reference { .. }
+        | Node::Abbreviation { .. }
+        | Node::LinkReferenceDefinition { .. }
+        | Node::InlineMath(_)
+        | Node::DisplayMath(_)
+        | Node::FrontMatter { .. } => node,
+    }
+}
+
+/// Assert that every opening tag in `html` is closed in the right order
+fn check_nesting(html: &str) {
+    let mut stack: Vec<&str> = Vec::new();
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        let after = &rest[open + 1..];
+        let close = after
+            .find('>')
+            .unwrap_or_else(|| panic!("unterminated tag in {:?}", html));
+        let tag = &after[..close];
+        rest = &after[close + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            match stack.pop() {
+                Some(expected) if expected == name => {}
+                other => panic!(
+                    "closing </{}> does not match {:?} in {:?}",
+                    name, other, html
+                ),
+            }
+        } else {
+            let name = tag
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or_default();
+            if VOID_ELEMENTS.contains(&name) {
+                assert!(tag.ends_with('/'), "void <{}> not self-closed", name);
+            } else {
+                stack.push(name);
+            }
+        }
+    }
+
+    assert!(stack.is_empty(), "unclosed tags {:?} in {:?}", stack, html);
+}
+
+fn load_seed_corpus() -> Vec<String> {
+    let mut entries: Vec<_> = fs::read_dir("tests/data/fuzz_corpus")
+        .expect("Failed to read fuzz corpus")
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    assert!(!entries.is_empty(), "fuzz corpus is empty");
+
+    entries
+        .iter()
+        .map(|path| fs::read_to_string(path).unwrap())
+        .collect()
+}
+
+#[test]
+fn seed_corpus() {
+    for markdown in load_seed_corpus() {
+        check_markdown_to_html(&markdown);
+        check_parse(&markdown);
+    }
+}
+
+#[test]
+#[ignore = "writes a test-fuzz corpus file per call; run before `cargo test-fuzz`"]
+fn record_corpus() {
+    for markdown in load_seed_corpus() {
+        markdown_to_html_target(&markdown);
+        parse_target(&markdown);
+    }
+}
+
+#[test]
+fn check_nesting_rejects_misnested_tags() {
+    check_nesting("<p><em>a</em><br /></p>\n");
+    assert!(std::panic::catch_unwind(|| check_nesting("<p><em>a</p></em>")).is_err());
+    assert!(std::panic::catch_unwind(|| check_nesting("<ul>\n<li>a</li>\n")).is_err());
+}
@@ -1,4 +1,6 @@
 use conformark::markdown_to_html;
+use conformark::parser::{Parser, ParserOptions};
+use conformark::renderer::HtmlRenderer;
 use serde::Deserialize;
 use std::fs;
 
@@ -63,3 +65,27 @@ fn commonmark_spec_tests() {
     // Once we start implementing, we'll make this strict
     eprintln!("\n  Note: Test harness is ready. Implementation can now proceed incrementally.");
 }
+
+#[test]
+fn spec_tests_with_reference_definition_nodes() {
+    // Keeping definitions in the tree must not change the rendered HTML
+    let test_data = fs::read_to_string("tests/data/tests.json").expect("Failed to read tests.json");
+    let tests: Vec<SpecTest> =
+        serde_json::from_str(&test_data).expect("Failed to parse tests.json");
+
+    let options = ParserOptions {
+        keep_reference_definitions: true,
+        ..Default::default()
+    };
+    let renderer = HtmlRenderer::new();
+
+    for test in tests.iter() {
+        let ast = Parser::with_options(options.clone()).parse(&test.markdown);
+        assert_eq!(
+            renderer.render(&ast),
+            markdown_to_html(&test.markdown),
+            "example {}",
+            test.example
+        );
+    }
+}